@@ -5,29 +5,74 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
 use fluxway_core::config::Config;
-use fluxway_core::layout::LayoutTree;
+use fluxway_core::layout::{LayoutTree, SplitDirection};
 use fluxway_core::state::Geometry;
 use fluxway_core::window::WindowId;
 
+const SIZES: &[u64] = &[1, 5, 10, 20, 50, 100, 1000, 2000];
+
+/// A tree of `n` windows, every tenth one opening a new vertical split so
+/// that the tree is nested rather than one flat row.
+fn nested_tree(n: u64, config: &Config) -> LayoutTree {
+    let mut tree = LayoutTree::new();
+    for i in 0..n {
+        let id = WindowId(i + 1);
+        tree.add_window(id, config);
+        if i % 10 == 9 {
+            tree.split(id, SplitDirection::Vertical);
+        }
+    }
+    tree
+}
+
 fn layout_calculation_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("layout");
+    let config = Config::default();
+    let area = Geometry::new(0, 0, 1920, 1080);
+
+    for &n in SIZES {
+        group.bench_with_input(BenchmarkId::new("calculate_tiling", n), &n, |b, &n| {
+            let mut tree = LayoutTree::new();
+            for i in 0..n {
+                tree.add_window(WindowId(i + 1), &config);
+            }
+            b.iter(|| {
+                tree.calculate_layout(black_box(area), 4);
+            });
+        });
+
+        // Every container changed: the whole tree is laid out again.
+        group.bench_with_input(BenchmarkId::new("relayout_all", n), &n, |b, &n| {
+            let mut tree = nested_tree(n, &config);
+            b.iter(|| {
+                for container in tree.containers.values_mut() {
+                    container.gap = 0;
+                }
+                tree.calculate_layout(black_box(area), 4);
+            });
+        });
+
+        // One window opens and closes: only its branch is laid out again.
+        group.bench_with_input(BenchmarkId::new("add_remove_window", n), &n, |b, &n| {
+            let mut tree = nested_tree(n, &config);
+            tree.calculate_layout(area, 4);
+            let extra = WindowId(n + 1);
+            b.iter(|| {
+                tree.add_window(extra, &config);
+                tree.calculate_layout(black_box(area), 4);
+                tree.remove_window(black_box(extra));
+                tree.calculate_layout(black_box(area), 4);
+            });
+        });
 
-    for num_windows in &[1, 5, 10, 20, 50, 100] {
-        group.bench_with_input(
-            BenchmarkId::new("calculate_tiling", num_windows),
-            num_windows,
-            |b, &n| {
-                let config = Config::default();
-                let mut tree = LayoutTree::new();
+        group.bench_with_input(BenchmarkId::new("container_of", n), &n, |b, &n| {
+            let tree = nested_tree(n, &config);
+            b.iter(|| {
                 for i in 0..n {
-                    tree.add_window(WindowId(i as u64 + 1), &config);
+                    black_box(tree.container_of(WindowId(i + 1)));
                 }
-                let area = Geometry::new(0, 0, 1920, 1080);
-                b.iter(|| {
-                    tree.calculate_layout(black_box(area), 4);
-                });
-            },
-        );
+            });
+        });
     }
 
     group.finish();
@@ -114,12 +114,32 @@ impl WinitBackend {
                 CoreAction::Exit => {
                     info!("Exit requested by core");
                 }
+                CoreAction::SetWindowFrame { id, frame, client } => {
+                    tracing::trace!("Frame for {id}: {frame:?}, client {client:?}");
+                }
+                CoreAction::SetWindowBorder { id, borders } => {
+                    tracing::trace!("Border for {id}: {borders:?}");
+                }
+                CoreAction::WindowDoesNotFit { id } => {
+                    tracing::trace!("{id} does not fit its tiled space");
+                }
+                CoreAction::SetDecorations { headers } => {
+                    tracing::trace!("{} tab and stack headers", headers.len());
+                }
+                CoreAction::SetVisible { id, visible } => {
+                    tracing::trace!("Visibility changed for {id}: {visible}");
+                }
                 CoreAction::SetFloating { id, floating } => {
                     tracing::trace!("Floating changed for {id}: {floating}");
                 }
                 CoreAction::WorkspaceChanged { active } => {
                     tracing::trace!("Workspace changed: {active:?}");
                 }
+                CoreAction::WorkspaceRenamed { id, old_name, name } => {
+                    tracing::trace!("Workspace {id} renamed from {old_name:?} to {name:?}");
+                    let event = fluxway_core::ipc::action_event(&self.core.state, action);
+                    tracing::trace!("IPC workspace event: {event:?}");
+                }
             }
         }
     }
@@ -10,8 +10,8 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use crate::layout::LayoutMode;
-use crate::window::{BorderStyle, WindowCriteria};
+use crate::layout::{LayoutMode, SplitDirection};
+use crate::window::{BorderStyle, Window, WindowCriteria};
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,6 +138,23 @@ impl Config {
             .unwrap_or_else(|_| String::from("# Error generating config"))
     }
 
+    /// The `[[workspaces]]` entry for the workspace called `name`, if any.
+    #[must_use]
+    pub fn workspace_config(&self, name: &str) -> Option<&WorkspaceConfigEntry> {
+        self.workspaces.iter().find(|entry| entry.name == name)
+    }
+
+    /// Where a new tiled `window` goes: the placement of the first rule
+    /// matching it that sets one, or else `general.new_window_placement`.
+    #[must_use]
+    pub fn placement_for(&self, window: &Window) -> NewWindowPlacement {
+        self.rules
+            .iter()
+            .filter(|rule| rule.criteria.matches(window))
+            .find_map(|rule| rule.placement)
+            .unwrap_or(self.general.new_window_placement)
+    }
+
     /// Get the socket path for IPC
     #[cfg(feature = "ipc")]
     pub fn socket_path(&self) -> PathBuf {
@@ -159,9 +176,11 @@ pub struct GeneralConfig {
     pub focus_follows_mouse: FocusFollowsMouse,
     /// Mouse warping behavior
     pub mouse_warping: MouseWarping,
-    /// Workspace back and forth
+    /// Ignored: `workspace back_and_forth` always works, as in i3. Kept so
+    /// that configs setting it still load.
     pub workspace_back_and_forth: bool,
-    /// Auto back and forth
+    /// Switching to, or moving a window to, the focused workspace goes to
+    /// the previously focused one instead
     pub workspace_auto_back_and_forth: bool,
     /// Force xwayland
     pub xwayland: XWaylandMode,
@@ -169,6 +188,14 @@ pub struct GeneralConfig {
     pub default_layout: LayoutMode,
     /// Default split direction
     pub default_orientation: Orientation,
+    /// Split each new window along the longer side of the focused window
+    pub autotiling: bool,
+    /// Where new tiled windows are inserted into the layout tree
+    pub new_window_placement: NewWindowPlacement,
+    /// Initial master area share for `master_stack` containers
+    pub master_ratio: f64,
+    /// Initial number of master windows for `master_stack` containers
+    pub master_count: usize,
     /// Modifier key for floating window drag
     pub floating_modifier: String,
     /// Socket path for IPC
@@ -195,6 +222,10 @@ impl Default for GeneralConfig {
             xwayland: XWaylandMode::Enable,
             default_layout: LayoutMode::Split,
             default_orientation: Orientation::Auto,
+            autotiling: false,
+            new_window_placement: NewWindowPlacement::AfterFocused,
+            master_ratio: 0.55,
+            master_count: 1,
             floating_modifier: "Mod4".to_string(),
             socket_path: None,
             popup_during_fullscreen: PopupDuringFullscreen::Smart,
@@ -242,6 +273,43 @@ pub enum Orientation {
     Auto,
 }
 
+impl Orientation {
+    /// Split direction for a new root container covering a `width`×`height`
+    /// area. `auto` splits along the longer side, so portrait outputs stack.
+    #[must_use]
+    pub const fn split_direction(self, width: u32, height: u32) -> SplitDirection {
+        match self {
+            Self::Horizontal => SplitDirection::Horizontal,
+            Self::Vertical => SplitDirection::Vertical,
+            Self::Auto => {
+                if height > width {
+                    SplitDirection::Vertical
+                } else {
+                    SplitDirection::Horizontal
+                }
+            }
+        }
+    }
+}
+
+/// Where a new tiled window goes in the layout tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NewWindowPlacement {
+    /// Right after the focused window, in its container.
+    #[default]
+    AfterFocused,
+    /// Last in the workspace's root container.
+    End,
+    /// First in the workspace's root container.
+    Start,
+    /// First in the focused container, i.e. the master of a `master_stack`.
+    AsMaster,
+    /// In a new container with the focused window, split along its longer
+    /// side.
+    SplitFocused,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum PopupDuringFullscreen {
@@ -313,6 +381,28 @@ impl Default for GapConfig {
     }
 }
 
+impl GapConfig {
+    /// The same gap between windows and at every screen edge.
+    #[must_use]
+    pub const fn uniform(gap: u32) -> Self {
+        Self {
+            inner: gap,
+            outer: gap,
+            top: None,
+            bottom: None,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Outer gap at each edge as `[top, right, bottom, left]`; an edge's
+    /// own setting overrides `outer`.
+    #[must_use]
+    pub fn outer_edges(&self) -> [u32; 4] {
+        [self.top, self.right, self.bottom, self.left].map(|edge| edge.unwrap_or(self.outer))
+    }
+}
+
 /// Border configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -387,7 +477,7 @@ impl Default for ColorConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WindowColors {
     pub border: String,
     pub background: String,
@@ -408,6 +498,15 @@ pub struct FontConfig {
     pub style: String,
 }
 
+impl FontConfig {
+    /// Height of a window title bar: one line of text with 4 px of padding
+    /// above and below.
+    #[must_use]
+    pub fn title_bar_height(&self) -> u32 {
+        (self.size * 1.5).ceil() as u32 + 8
+    }
+}
+
 impl Default for FontConfig {
     fn default() -> Self {
         Self {
@@ -545,12 +644,19 @@ pub enum BackgroundMode {
 pub struct WorkspaceConfigEntry {
     /// Workspace name/number
     pub name: String,
-    /// Assign to specific output
+    /// Output to show the workspace on while it is connected
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<String>,
-    /// Custom gaps
+    /// Custom gaps, used both between windows and at the screen edges
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gaps: Option<u32>,
+    /// Layout for the workspace's root container
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<LayoutMode>,
+    /// Split direction for the workspace's root container, overriding
+    /// `general.default_orientation`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<Orientation>,
 }
 
 /// Key binding configuration
@@ -584,7 +690,12 @@ pub struct WindowRule {
     /// Matching criteria
     pub criteria: WindowCriteria,
     /// Commands to execute
+    #[serde(default)]
     pub commands: Vec<String>,
+    /// Where matching windows are inserted, overriding
+    /// `general.new_window_placement`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<NewWindowPlacement>,
 }
 
 /// Startup command
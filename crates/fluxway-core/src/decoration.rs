@@ -0,0 +1,190 @@
+//! Tab and stack headers.
+//!
+//! After each layout pass the core describes the header bars of the
+//! tabbed and stacked containers on screen: where each tab goes, its title,
+//! its state and the colours to draw it in. Backends draw these as they
+//! are, without walking the layout tree themselves.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use crate::config::{ColorConfig, WindowColors};
+use crate::layout::{
+    split_evenly, ContainerId, LayoutMode, LayoutNode, LayoutTree, SplitDirection,
+};
+use crate::state::Geometry;
+use crate::window::{Window, WindowId, WindowState};
+
+/// How a tab is drawn, following i3's colour classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabState {
+    /// Holds the focused window.
+    Focused,
+    /// The container's focused child, while focus is elsewhere.
+    FocusedInactive,
+    Unfocused,
+    /// Holds a window with the urgent hint set.
+    Urgent,
+}
+
+impl TabState {
+    #[must_use]
+    pub const fn colors(self, colors: &ColorConfig) -> &WindowColors {
+        match self {
+            Self::Focused => &colors.focused,
+            Self::FocusedInactive => &colors.focused_inactive,
+            Self::Unfocused => &colors.unfocused,
+            Self::Urgent => &colors.urgent,
+        }
+    }
+}
+
+/// One tab of a header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tab {
+    /// The window the tab shows: the child itself, or the focused window
+    /// inside a child container.
+    pub window: Option<WindowId>,
+    pub title: String,
+    pub state: TabState,
+    pub geometry: Geometry,
+    pub colors: WindowColors,
+}
+
+/// The header bar of a tabbed or stacked container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub container: ContainerId,
+    pub layout: LayoutMode,
+    pub geometry: Geometry,
+    pub tabs: Vec<Tab>,
+}
+
+/// Headers of the tabbed and stacked containers `tree` laid out last,
+/// with `focused` as the focused window.
+#[must_use]
+pub fn headers<S: BuildHasher>(
+    tree: &LayoutTree,
+    windows: &HashMap<WindowId, Window, S>,
+    focused: Option<WindowId>,
+    colors: &ColorConfig,
+) -> Vec<Header> {
+    let height = tree.header_height;
+    let mut headers = Vec::new();
+    for &container_id in &tree.tab_containers {
+        let Some(container) = tree.containers.get(container_id) else {
+            continue;
+        };
+        let n = container.children.len() as u32;
+        let area = container.geometry;
+        let (geometry, slots) = if container.layout == LayoutMode::Stacked {
+            let geometry = Geometry::new(area.x, area.y, area.width, height * n);
+            (
+                geometry,
+                split_evenly(geometry, n, 0, SplitDirection::Vertical),
+            )
+        } else {
+            let geometry = Geometry::new(area.x, area.y, area.width, height);
+            (
+                geometry,
+                split_evenly(geometry, n, 0, SplitDirection::Horizontal),
+            )
+        };
+
+        let tabs = container
+            .children
+            .iter()
+            .zip(slots)
+            .enumerate()
+            .map(|(i, (&child, slot))| {
+                let state = tab_state(tree, windows, child, focused, i == container.focused_child);
+                let window = tree.descend_focused(child);
+                Tab {
+                    window,
+                    title: window
+                        .and_then(|id| windows.get(&id))
+                        .map(|w| w.title.clone())
+                        .unwrap_or_default(),
+                    state,
+                    geometry: slot,
+                    colors: state.colors(colors).clone(),
+                }
+            })
+            .collect();
+        headers.push(Header {
+            container: container_id,
+            layout: container.layout,
+            geometry,
+            tabs,
+        });
+    }
+    headers
+}
+
+fn tab_state<S: BuildHasher>(
+    tree: &LayoutTree,
+    windows: &HashMap<WindowId, Window, S>,
+    child: LayoutNode,
+    focused: Option<WindowId>,
+    is_focused_child: bool,
+) -> TabState {
+    let inside = tree.windows_in(child);
+    let urgent = inside
+        .iter()
+        .filter_map(|id| windows.get(id))
+        .any(|w| w.state.contains(WindowState::URGENT));
+    if urgent {
+        TabState::Urgent
+    } else if focused.is_some_and(|id| inside.contains(&id)) {
+        TabState::Focused
+    } else if is_focused_child {
+        TabState::FocusedInactive
+    } else {
+        TabState::Unfocused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_tabbed_headers() {
+        let config = Config::default();
+        let mut tree = LayoutTree::new();
+        let mut windows = HashMap::new();
+        for id in 1..=3 {
+            let window = Window::new(WindowId(id), "term".into(), format!("Term {id}"));
+            tree.add_window(window.id, &config);
+            windows.insert(window.id, window);
+        }
+        let root_id = tree.root.unwrap();
+        tree.containers.get_mut(root_id).unwrap().layout = LayoutMode::Tabbed;
+        windows
+            .get_mut(&WindowId(1))
+            .unwrap()
+            .state
+            .insert(WindowState::URGENT);
+        tree.calculate_layout(Geometry::new(0, 0, 900, 600), 0);
+
+        let headers = headers(&tree, &windows, Some(WindowId(3)), &config.colors);
+        assert_eq!(headers.len(), 1);
+        let header = &headers[0];
+        assert_eq!(
+            header.geometry,
+            Geometry::new(0, 0, 900, tree.header_height)
+        );
+        let states: Vec<TabState> = header.tabs.iter().map(|tab| tab.state).collect();
+        assert_eq!(
+            states,
+            [TabState::Urgent, TabState::Unfocused, TabState::Focused]
+        );
+        assert_eq!(header.tabs[2].title, "Term 3");
+        assert_eq!(header.tabs[1].geometry.x, 300);
+        assert_eq!(
+            header.tabs[2].colors.background,
+            config.colors.focused.background
+        );
+    }
+}
@@ -3,8 +3,9 @@
 //! [`CoreEvent`] represents what the backend tells core.
 //! [`CoreAction`] represents what core tells the backend to do.
 
+use crate::decoration::Header;
 use crate::state::Geometry;
-use crate::window::WindowId;
+use crate::window::{Borders, SizeHints, WindowId};
 use crate::workspace::WorkspaceId;
 
 /// Events that a backend sends to the core engine.
@@ -32,6 +33,10 @@ pub enum CoreEvent {
         new_geometry_hint: Option<Geometry>,
     },
 
+    /// A window set new size constraints (minimum/maximum size, resize
+    /// increments).
+    WindowSizeHints { id: WindowId, hints: SizeHints },
+
     /// A window is requesting focus (e.g., urgent hint).
     FocusRequested { id: WindowId },
 
@@ -70,18 +75,50 @@ pub enum CoreAction {
         h: u32,
     },
 
+    /// Place the decorations of a tiled window. The client surface goes in
+    /// `client`; the border and title bar fill the rest of `frame`, which
+    /// matches the window's `SetWindowGeometry`.
+    SetWindowFrame {
+        id: WindowId,
+        frame: Geometry,
+        client: Geometry,
+    },
+
+    /// Set the border drawn around a tiled window.
+    SetWindowBorder { id: WindowId, borders: Borders },
+
+    /// A tiled window got less than its minimum size because its
+    /// container cannot fit the minimums of all its children.
+    WindowDoesNotFit { id: WindowId },
+
+    /// Replace the tab and stack headers drawn on the visible workspaces.
+    /// An empty list clears them.
+    SetDecorations { headers: Vec<Header> },
+
     /// Set keyboard focus to a window (or clear focus if `None`).
     SetFocus { id: Option<WindowId> },
 
     /// Request that the backend close a window.
     RequestClose { id: WindowId },
 
+    /// Show or hide a window, e.g. as it scrolls in or out of the viewport
+    /// of a scrolling workspace.
+    SetVisible { id: WindowId, visible: bool },
+
     /// Notify the backend that a window's floating state changed.
     SetFloating { id: WindowId, floating: bool },
 
     /// The active workspace changed.
     WorkspaceChanged { active: Option<WorkspaceId> },
 
+    /// A workspace was renamed. Frontends send it to IPC clients as the
+    /// workspace `rename` event `ipc::action_event` builds.
+    WorkspaceRenamed {
+        id: WorkspaceId,
+        old_name: String,
+        name: String,
+    },
+
     /// The backend should spawn a child process.
     SpawnProcess { command: String },
 
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use bitflags::bitflags;
 use thiserror::Error;
 
-use crate::config::BindingConfig;
+use crate::config::{BindingConfig, GapConfig};
 
 /// Input handling errors
 
@@ -443,7 +443,7 @@ pub enum Command {
     Kill,
     Focus(FocusTarget),
     Move(MoveTarget),
-    Resize(ResizeDirection, i32),
+    Resize(ResizeDirection, ResizeAmount),
     Floating(Toggle),
     Fullscreen(Toggle),
     Sticky(Toggle),
@@ -451,10 +451,20 @@ pub enum Command {
     // Layout
     Split(SplitCmd),
     Layout(LayoutCmd),
+    Master(MasterCmd),
+    Column(ColumnCmd),
+    /// `append_layout <file>` — load a saved layout with placeholders.
+    AppendLayout(String),
 
     // Workspace
     Workspace(WorkspaceTarget),
     MoveToWorkspace(WorkspaceTarget),
+    /// `rename workspace [<old>] to <new>` — the focused workspace if no
+    /// old name is given.
+    RenameWorkspace {
+        from: Option<String>,
+        to: String,
+    },
 
     // Scratchpad
     ScratchpadShow,
@@ -474,7 +484,7 @@ pub enum Command {
     Exit,
 
     // Gaps
-    Gaps(GapCmd),
+    Gaps(GapCmd, GapScope),
 
     // Bar
     Bar(BarCmd),
@@ -498,6 +508,8 @@ pub enum FocusTarget {
     Down,
     Parent,
     Child,
+    ColumnLeft,
+    ColumnRight,
     ModeToggle,
     Output(String),
     Workspace,
@@ -519,10 +531,51 @@ pub enum MoveTarget {
 pub enum ResizeDirection {
     Width(ResizeOp),
     Height(ResizeOp),
-    Left,
-    Right,
-    Up,
-    Down,
+    Left(ResizeOp),
+    Right(ResizeOp),
+    Up(ResizeOp),
+    Down(ResizeOp),
+}
+
+impl ResizeDirection {
+    #[must_use]
+    pub const fn op(self) -> ResizeOp {
+        match self {
+            Self::Width(op)
+            | Self::Height(op)
+            | Self::Left(op)
+            | Self::Right(op)
+            | Self::Up(op)
+            | Self::Down(op) => op,
+        }
+    }
+}
+
+/// Resize amount, in pixels or percentage points of the parent container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeAmount {
+    Px(i32),
+    Ppt(i32),
+}
+
+impl ResizeAmount {
+    /// Parse `"10"`, `"10px"`, `"10 px"` or `"10 ppt"` (unit defaults to px).
+    fn parse(value: &str, unit: Option<&str>) -> Option<Self> {
+        let split = value
+            .find(|c: char| !c.is_ascii_digit() && c != '-')
+            .unwrap_or(value.len());
+        let (number, suffix) = value.split_at(split);
+        let number = number.parse::<i32>().ok()?;
+        let unit = if suffix.is_empty() {
+            unit.unwrap_or("px")
+        } else {
+            suffix
+        };
+        match unit.to_lowercase().as_str() {
+            "ppt" => Some(Self::Ppt(number)),
+            _ => Some(Self::Px(number)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -550,6 +603,59 @@ pub enum LayoutCmd {
     Toggle,
     ToggleSplit,
     ToggleAll,
+    MasterStack,
+    Dwindle,
+    Grid,
+    Scrolling,
+}
+
+/// Master–stack layout adjustments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MasterCmd {
+    /// `master ratio +0.05` — the master area's share, in hundredths.
+    Ratio(Adjust),
+    /// `master count +1`
+    Count(Adjust),
+    /// `swap master`
+    Swap,
+}
+
+/// Column commands for the scrolling layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnCmd {
+    /// `column width cycle` — step through the preset widths.
+    CycleWidth,
+    /// `column consume` — pull the next column's window into this one.
+    Consume,
+    /// `column expel` — move the focused window into its own column.
+    Expel,
+}
+
+/// An absolute (`0.6`) or relative (`+0.05`, `-1`) adjustment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjust {
+    Set(i32),
+    By(i32),
+}
+
+impl Adjust {
+    /// Parse `value`, scaling it into integer units (100 for hundredths).
+    fn parse(value: &str, scale: f64) -> Option<Self> {
+        let amount = (value.parse::<f64>().ok()? * scale).round() as i32;
+        if value.starts_with(['+', '-']) {
+            Some(Self::By(amount))
+        } else {
+            Some(Self::Set(amount))
+        }
+    }
+
+    #[must_use]
+    pub const fn apply(self, current: i32) -> i32 {
+        match self {
+            Self::Set(value) => value,
+            Self::By(delta) => current.saturating_add(delta),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -583,6 +689,71 @@ pub enum GapOp {
     Toggle(i32),
 }
 
+impl GapCmd {
+    /// Apply the command to `gaps`. `outer`, `horizontal` and `vertical`
+    /// change each edge they cover.
+    pub fn apply(&self, gaps: &mut GapConfig) {
+        let [top, right, bottom, left] = gaps.outer_edges();
+        match self {
+            Self::Inner(op) => gaps.inner = op.apply(gaps.inner),
+            Self::Outer(op) => {
+                gaps.outer = op.apply(gaps.outer);
+                for edge in [
+                    &mut gaps.top,
+                    &mut gaps.right,
+                    &mut gaps.bottom,
+                    &mut gaps.left,
+                ] {
+                    *edge = edge.map(|value| op.apply(value));
+                }
+            }
+            Self::Horizontal(op) => {
+                gaps.left = Some(op.apply(left));
+                gaps.right = Some(op.apply(right));
+            }
+            Self::Vertical(op) => {
+                gaps.top = Some(op.apply(top));
+                gaps.bottom = Some(op.apply(bottom));
+            }
+            Self::Top(op) => gaps.top = Some(op.apply(top)),
+            Self::Right(op) => gaps.right = Some(op.apply(right)),
+            Self::Bottom(op) => gaps.bottom = Some(op.apply(bottom)),
+            Self::Left(op) => gaps.left = Some(op.apply(left)),
+        }
+    }
+}
+
+impl GapOp {
+    /// The gap after applying this operation to `current`. `toggle`
+    /// switches between zero and its amount.
+    #[must_use]
+    pub fn apply(&self, current: u32) -> u32 {
+        let current = i32::try_from(current).unwrap_or(i32::MAX);
+        let value = match *self {
+            Self::Set(n) => n,
+            Self::Plus(n) => current.saturating_add(n),
+            Self::Minus(n) => current.saturating_sub(n),
+            Self::Toggle(n) => {
+                if current == 0 {
+                    n
+                } else {
+                    0
+                }
+            }
+        };
+        value.max(0) as u32
+    }
+}
+
+/// Which workspaces a `gaps` command changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapScope {
+    /// The focused workspace only.
+    Current,
+    /// The global setting, dropping per-workspace overrides.
+    All,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BarCmd {
     Mode(String),
@@ -610,8 +781,13 @@ impl Command {
                 "down" => Self::Focus(FocusTarget::Down),
                 "parent" => Self::Focus(FocusTarget::Parent),
                 "child" => Self::Focus(FocusTarget::Child),
+                "column left" => Self::Focus(FocusTarget::ColumnLeft),
+                "column right" => Self::Focus(FocusTarget::ColumnRight),
                 "mode_toggle" => Self::Focus(FocusTarget::ModeToggle),
-                _ => Self::Unknown(s.to_string()),
+                _ => args.strip_prefix("output ").map_or_else(
+                    || Self::Unknown(s.to_string()),
+                    |output| Self::Focus(FocusTarget::Output(output.trim().to_string())),
+                ),
             },
 
             "move" => Self::parse_move(args),
@@ -647,6 +823,10 @@ impl Command {
                 "toggle" => Self::Layout(LayoutCmd::Toggle),
                 "toggle split" => Self::Layout(LayoutCmd::ToggleSplit),
                 "toggle all" => Self::Layout(LayoutCmd::ToggleAll),
+                "master_stack" => Self::Layout(LayoutCmd::MasterStack),
+                "dwindle" => Self::Layout(LayoutCmd::Dwindle),
+                "grid" => Self::Layout(LayoutCmd::Grid),
+                "scrolling" => Self::Layout(LayoutCmd::Scrolling),
                 _ => Self::Unknown(s.to_string()),
             },
 
@@ -665,6 +845,8 @@ impl Command {
                 },
             },
 
+            "rename" => Self::parse_rename(args),
+
             "scratchpad" => match args.to_lowercase().as_str() {
                 "show" => Self::ScratchpadShow,
                 _ => Self::Unknown(s.to_string()),
@@ -685,6 +867,22 @@ impl Command {
 
             "resize" => Self::parse_resize(args),
 
+            "gaps" => Self::parse_gaps(args),
+
+            "append_layout" if !args.is_empty() => Self::AppendLayout(args.to_string()),
+
+            "master" => Self::parse_master(args),
+            "column" => match args.to_lowercase().as_str() {
+                "width cycle" => Self::Column(ColumnCmd::CycleWidth),
+                "consume" => Self::Column(ColumnCmd::Consume),
+                "expel" => Self::Column(ColumnCmd::Expel),
+                _ => Self::Unknown(s.to_string()),
+            },
+            "swap" => match args.to_lowercase().as_str() {
+                "master" => Self::Master(MasterCmd::Swap),
+                _ => Self::Unknown(s.to_string()),
+            },
+
             _ => Self::Unknown(s.to_string()),
         }
     }
@@ -711,6 +909,8 @@ impl Command {
                     } else {
                         Self::MoveToWorkspace(WorkspaceTarget::Name(ws))
                     }
+                } else if parts.len() >= 4 && parts[1] == "to" && parts[2] == "output" {
+                    Self::Move(MoveTarget::Output(parts[3..].join(" ")))
                 } else {
                     Self::Unknown(format!("move {args}"))
                 }
@@ -730,6 +930,40 @@ impl Command {
         }
     }
 
+    /// `rename workspace [<old>] to <new>`
+    fn parse_rename(args: &str) -> Self {
+        let unknown = || Self::Unknown(format!("rename {args}"));
+        let Some(rest) = args.strip_prefix("workspace ") else {
+            return unknown();
+        };
+        let rest = rest.trim_start();
+        let (from, to) = if let Some(to) = rest.strip_prefix("to ") {
+            (None, to)
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            // A quoted name may itself contain " to ".
+            let Some((from, to)) = quoted
+                .split_once('"')
+                .and_then(|(from, after)| Some((from, after.trim_start().strip_prefix("to ")?)))
+            else {
+                return unknown();
+            };
+            (Some(from.to_string()), to)
+        } else {
+            match rest.split_once(" to ") {
+                Some((from, to)) => (Some(unquote(from.trim()).to_string()), to),
+                None => return unknown(),
+            }
+        };
+        let to = unquote(to.trim());
+        if to.is_empty() {
+            return unknown();
+        }
+        Self::RenameWorkspace {
+            from,
+            to: to.to_string(),
+        }
+    }
+
     fn parse_resize(args: &str) -> Self {
         let parts: Vec<&str> = args.split_whitespace().collect();
 
@@ -747,21 +981,66 @@ impl Command {
         let direction = match parts[1].to_lowercase().as_str() {
             "width" => ResizeDirection::Width(op),
             "height" => ResizeDirection::Height(op),
-            "left" => ResizeDirection::Left,
-            "right" => ResizeDirection::Right,
-            "up" => ResizeDirection::Up,
-            "down" => ResizeDirection::Down,
+            "left" => ResizeDirection::Left(op),
+            "right" => ResizeDirection::Right(op),
+            "up" => ResizeDirection::Up(op),
+            "down" => ResizeDirection::Down(op),
             _ => return Self::Unknown(format!("resize {args}")),
         };
 
-        let amount = if parts.len() >= 3 {
-            parts[2].trim_end_matches("px").parse::<i32>().unwrap_or(10)
-        } else {
-            10
-        };
+        let amount = parts
+            .get(2)
+            .and_then(|value| ResizeAmount::parse(value, parts.get(3).copied()))
+            .unwrap_or(ResizeAmount::Px(10));
 
         Self::Resize(direction, amount)
     }
+
+    /// `gaps inner|outer|horizontal|vertical|top|right|bottom|left
+    /// current|all set|plus|minus|toggle <px>`
+    fn parse_gaps(args: &str) -> Self {
+        let parts: Vec<String> = args.split_whitespace().map(str::to_lowercase).collect();
+        let [kind, scope, op, amount] = parts.as_slice() else {
+            return Self::Unknown(format!("gaps {args}"));
+        };
+        let Ok(amount) = amount.trim_end_matches("px").parse::<i32>() else {
+            return Self::Unknown(format!("gaps {args}"));
+        };
+        let scope = match scope.as_str() {
+            "current" => GapScope::Current,
+            "all" => GapScope::All,
+            _ => return Self::Unknown(format!("gaps {args}")),
+        };
+        let op = match op.as_str() {
+            "set" => GapOp::Set(amount),
+            "plus" => GapOp::Plus(amount),
+            "minus" => GapOp::Minus(amount),
+            "toggle" => GapOp::Toggle(amount),
+            _ => return Self::Unknown(format!("gaps {args}")),
+        };
+        let cmd = match kind.as_str() {
+            "inner" => GapCmd::Inner(op),
+            "outer" => GapCmd::Outer(op),
+            "horizontal" => GapCmd::Horizontal(op),
+            "vertical" => GapCmd::Vertical(op),
+            "top" => GapCmd::Top(op),
+            "right" => GapCmd::Right(op),
+            "bottom" => GapCmd::Bottom(op),
+            "left" => GapCmd::Left(op),
+            _ => return Self::Unknown(format!("gaps {args}")),
+        };
+        Self::Gaps(cmd, scope)
+    }
+
+    fn parse_master(args: &str) -> Self {
+        let parts: Vec<&str> = args.split_whitespace().collect();
+        let cmd = match parts.as_slice() {
+            ["ratio", value] => Adjust::parse(value, 100.0).map(MasterCmd::Ratio),
+            ["count", value] => Adjust::parse(value, 1.0).map(MasterCmd::Count),
+            _ => None,
+        };
+        cmd.map_or_else(|| Self::Unknown(format!("master {args}")), Self::Master)
+    }
 }
 
 /// Binding mode (like resize mode in i3)
@@ -954,6 +1233,14 @@ impl InputManager {
     }
 }
 
+/// `value` without the double quotes around it, if it has them.
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -983,6 +1270,92 @@ mod tests {
             cmd,
             Command::Workspace(WorkspaceTarget::Number(3))
         ));
+
+        let cmd = Command::parse("resize grow width 10 px");
+        assert_eq!(
+            cmd,
+            Command::Resize(ResizeDirection::Width(ResizeOp::Grow), ResizeAmount::Px(10))
+        );
+
+        let cmd = Command::parse("resize shrink left 5ppt");
+        assert_eq!(
+            cmd,
+            Command::Resize(ResizeDirection::Left(ResizeOp::Shrink), ResizeAmount::Ppt(5))
+        );
+
+        let cmd = Command::parse("master ratio +0.05");
+        assert_eq!(cmd, Command::Master(MasterCmd::Ratio(Adjust::By(5))));
+
+        let cmd = Command::parse("master count 2");
+        assert_eq!(cmd, Command::Master(MasterCmd::Count(Adjust::Set(2))));
+
+        let cmd = Command::parse("layout grid");
+        assert_eq!(cmd, Command::Layout(LayoutCmd::Grid));
+
+        let cmd = Command::parse("swap master");
+        assert_eq!(cmd, Command::Master(MasterCmd::Swap));
+
+        let cmd = Command::parse("focus column left");
+        assert_eq!(cmd, Command::Focus(FocusTarget::ColumnLeft));
+
+        let cmd = Command::parse("column width cycle");
+        assert_eq!(cmd, Command::Column(ColumnCmd::CycleWidth));
+
+        let cmd = Command::parse("gaps outer current toggle 40");
+        assert_eq!(
+            cmd,
+            Command::Gaps(GapCmd::Outer(GapOp::Toggle(40)), GapScope::Current)
+        );
+
+        let cmd = Command::parse("append_layout /tmp/dev.json");
+        assert_eq!(cmd, Command::AppendLayout("/tmp/dev.json".into()));
+
+        let cmd = Command::parse("rename workspace to 3:mail");
+        assert_eq!(
+            cmd,
+            Command::RenameWorkspace {
+                from: None,
+                to: "3:mail".into()
+            }
+        );
+
+        let cmd = Command::parse("rename workspace 2 to web browser");
+        assert_eq!(
+            cmd,
+            Command::RenameWorkspace {
+                from: Some("2".into()),
+                to: "web browser".into()
+            }
+        );
+
+        let cmd = Command::parse(r#"rename workspace "my to do" to "2: web""#);
+        assert_eq!(
+            cmd,
+            Command::RenameWorkspace {
+                from: Some("my to do".into()),
+                to: "2: web".into()
+            }
+        );
+
+        let cmd = Command::parse("focus output HDMI-A-1");
+        assert_eq!(cmd, Command::Focus(FocusTarget::Output("HDMI-A-1".into())));
+
+        let cmd = Command::parse("move container to output right");
+        assert_eq!(cmd, Command::Move(MoveTarget::Output("right".into())));
+    }
+
+    #[test]
+    fn test_gap_ops_saturate() {
+        assert_eq!(GapOp::Plus(i32::MAX).apply(10), i32::MAX as u32);
+        assert_eq!(GapOp::Minus(i32::MAX).apply(u32::MAX), 0);
+        assert_eq!(GapOp::Minus(5).apply(3), 0);
+    }
+
+    #[test]
+    fn test_adjust_saturates() {
+        assert_eq!(Adjust::By(i32::MAX).apply(55), i32::MAX);
+        assert_eq!(Adjust::By(i32::MIN).apply(-1), i32::MIN);
+        assert_eq!(Adjust::Set(3).apply(i32::MAX), 3);
     }
 
     #[test]
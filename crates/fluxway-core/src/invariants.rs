@@ -22,6 +22,12 @@ pub enum InvariantError {
 
     #[error("Mark '{0}' points to non-existent window")]
     MarkPointsToMissing(String),
+
+    #[error("Layout tree of workspace {0} indexes a window under the wrong container")]
+    WindowIndexStale(String),
+
+    #[error("Output {0} lists or shows a workspace bound to another output")]
+    OutputWorkspaceMismatch(String),
 }
 
 /// Validate all core invariants. Returns the first violation found.
@@ -66,5 +72,27 @@ pub fn validate(state: &State) -> Result<(), InvariantError> {
         }
     }
 
+    // 5. Each layout tree's window index matches its containers
+    for ws in state.workspaces.values() {
+        if !ws.layout.index_is_consistent() {
+            return Err(InvariantError::WindowIndexStale(ws.name.clone()));
+        }
+    }
+
+    // 6. Each output lists and shows only the workspaces bound to it
+    for output in state.outputs.values() {
+        let bound_here = |ws_id| {
+            state
+                .workspaces
+                .get(ws_id)
+                .is_some_and(|ws| ws.output.as_ref() == Some(&output.name))
+        };
+        if !output.workspaces.iter().all(bound_here)
+            || !output.active_workspace.iter().all(bound_here)
+        {
+            return Err(InvariantError::OutputWorkspaceMismatch(output.name.clone()));
+        }
+    }
+
     Ok(())
 }
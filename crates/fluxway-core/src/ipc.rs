@@ -0,0 +1,376 @@
+//! IPC views of the core state.
+//!
+//! Serializable snapshots handed to IPC clients (`get_tree` and friends).
+//! The socket transport itself lives in the frontends.
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::CoreAction;
+use crate::layout::{LayoutMode, LayoutNode, SplitDirection};
+use crate::scrolling::Column;
+use crate::state::{Geometry, State};
+use crate::window::{BorderStyle, Window, WindowId, WindowState};
+use crate::workspace::{Workspace, WorkspaceId};
+
+/// Rectangle for IPC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<Geometry> for Rect {
+    fn from(g: Geometry) -> Self {
+        Self {
+            x: g.x,
+            y: g.y,
+            width: g.width,
+            height: g.height,
+        }
+    }
+}
+
+/// Tree node for IPC, as in i3's `get_tree` reply.
+///
+/// `window_rect` and `deco_rect` are relative to `rect`; `geometry` is
+/// the size the client asked for. `focus` lists the child IDs, the
+/// focused one first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub id: u64,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub border: String,
+    pub current_border_width: i32,
+    pub layout: String,
+    pub orientation: String,
+    pub rect: Rect,
+    pub window_rect: Rect,
+    pub deco_rect: Rect,
+    pub geometry: Rect,
+    pub urgent: bool,
+    pub sticky: bool,
+    pub focused: bool,
+    pub focus: Vec<u64>,
+    pub nodes: Vec<Self>,
+    pub floating_nodes: Vec<Self>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+}
+
+impl TreeNode {
+    fn new(id: u64, name: Option<String>, node_type: &str, rect: Rect) -> Self {
+        Self {
+            id,
+            name,
+            node_type: node_type.to_string(),
+            border: "none".to_string(),
+            current_border_width: 0,
+            layout: "none".to_string(),
+            orientation: "none".to_string(),
+            rect,
+            window_rect: Rect::default(),
+            deco_rect: Rect::default(),
+            geometry: Rect::default(),
+            urgent: false,
+            sticky: false,
+            focused: false,
+            focus: Vec::new(),
+            nodes: Vec::new(),
+            floating_nodes: Vec::new(),
+            app_id: None,
+            pid: None,
+        }
+    }
+
+    /// Depth-first search for the node with `focused` set.
+    pub fn find_focused(&self) -> Option<&Self> {
+        if self.focused {
+            return Some(self);
+        }
+        self.nodes
+            .iter()
+            .chain(&self.floating_nodes)
+            .find_map(Self::find_focused)
+    }
+
+    /// Fill `focus` from the children, putting `first` in front.
+    fn set_focus(&mut self, first: Option<u64>) {
+        let children = self.nodes.iter().chain(&self.floating_nodes).map(|n| n.id);
+        self.focus = first
+            .into_iter()
+            .chain(children.filter(|&id| Some(id) != first))
+            .collect();
+    }
+}
+
+/// A `workspace` event for IPC subscribers, shaped like i3's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceEvent {
+    pub change: String,
+    pub current: Option<TreeNode>,
+    pub old: Option<TreeNode>,
+}
+
+/// The `workspace` event reporting `change` (say, `"rename"`) to
+/// `workspace_id`.
+#[must_use]
+pub fn workspace_event(state: &State, change: &str, workspace_id: WorkspaceId) -> WorkspaceEvent {
+    WorkspaceEvent {
+        change: change.to_string(),
+        current: state
+            .workspaces
+            .get(&workspace_id)
+            .map(|ws| workspace_node(state, ws)),
+        old: None,
+    }
+}
+
+/// The `workspace` event subscribers are sent for `action`, if it is one
+/// they hear of.
+#[must_use]
+pub fn action_event(state: &State, action: &CoreAction) -> Option<WorkspaceEvent> {
+    match action {
+        CoreAction::WorkspaceRenamed { id, .. } => Some(workspace_event(state, "rename", *id)),
+        _ => None,
+    }
+}
+
+/// Build the `get_tree` reply: root → workspaces → containers → windows.
+///
+/// Exactly one node is reported as focused: the container selected with
+/// `focus parent` if there is one, otherwise the focused window.
+#[must_use]
+pub fn get_tree(state: &State) -> TreeNode {
+    let mut root = TreeNode::new(
+        0,
+        Some("root".to_string()),
+        "root",
+        Rect::from(Geometry::default()),
+    );
+    root.nodes = state
+        .workspaces
+        .values()
+        .map(|ws| workspace_node(state, ws))
+        .collect();
+    root.set_focus(state.focus.focused_workspace.map(|id| u64::from(id.0)));
+    root
+}
+
+fn workspace_node(state: &State, ws: &Workspace) -> TreeNode {
+    let mut node = TreeNode::new(
+        u64::from(ws.id.0),
+        Some(ws.name.clone()),
+        "workspace",
+        Rect::from(ws.geometry),
+    );
+    if let Some(scrolling) = &ws.scrolling {
+        node.layout = "scrolling".to_string();
+        node.nodes = scrolling
+            .columns
+            .iter()
+            .map(|column| column_node(state, ws, column))
+            .collect();
+    } else if let Some(root) = ws.layout.root {
+        node.nodes
+            .push(layout_node(state, ws, LayoutNode::Container(root)));
+    }
+    node.floating_nodes = ws
+        .floating_windows
+        .iter()
+        .map(|&id| window_node(state, ws, id))
+        .collect();
+    node.urgent = ws.windows().any(|id| {
+        state
+            .windows
+            .get(&id)
+            .is_some_and(|w| w.state.contains(WindowState::URGENT))
+    });
+    // A floating window in focus comes first, else the tiled tree.
+    let floating_focus = ws
+        .focus_stack
+        .last()
+        .filter(|id| ws.floating_windows.contains(id))
+        .map(|id| id.0);
+    let tiled = node.nodes.first().map(|n| n.id);
+    node.set_focus(floating_focus.or(tiled));
+    node
+}
+
+/// A column of the scrolling layout, reported as a vertical split.
+fn column_node(state: &State, ws: &Workspace, column: &Column) -> TreeNode {
+    let windows: Vec<TreeNode> = column
+        .windows
+        .iter()
+        .map(|&id| window_node(state, ws, id))
+        .collect();
+    let rect = match (windows.first(), windows.last()) {
+        (Some(first), Some(last)) => Rect {
+            height: (last.rect.y + last.rect.height as i32 - first.rect.y).max(0) as u32,
+            ..first.rect
+        },
+        _ => Rect::from(Geometry::default()),
+    };
+    let mut node = TreeNode::new(column.id.0, None, "con", rect);
+    node.layout = "splitv".to_string();
+    node.orientation = "vertical".to_string();
+    node.nodes = windows;
+    node.set_focus(None);
+    node
+}
+
+fn layout_node(state: &State, ws: &Workspace, node: LayoutNode) -> TreeNode {
+    let tree = &ws.layout;
+    let container_id = match node {
+        LayoutNode::Window(id) => return window_node(state, ws, id),
+        LayoutNode::Container(id) => id,
+    };
+    let Some(container) = tree.containers.get(container_id) else {
+        return TreeNode::new(container_id.0, None, "con", Rect::from(Geometry::default()));
+    };
+
+    let mut node = TreeNode::new(container_id.0, None, "con", Rect::from(container.geometry));
+    node.layout = match (container.layout, container.split_direction) {
+        (LayoutMode::Split, SplitDirection::Horizontal) => "splith",
+        (LayoutMode::Split, SplitDirection::Vertical) => "splitv",
+        (LayoutMode::Tabbed, _) => "tabbed",
+        (LayoutMode::Stacked, _) => "stacked",
+        (LayoutMode::MasterStack, _) => "master_stack",
+        (LayoutMode::Dwindle, _) => "dwindle",
+        (LayoutMode::Grid, _) => "grid",
+    }
+    .to_string();
+    node.orientation = match container.split_direction {
+        SplitDirection::Horizontal => "horizontal",
+        SplitDirection::Vertical => "vertical",
+    }
+    .to_string();
+    node.focused = state.focus.focused_container == Some(container_id);
+    node.nodes = container
+        .children
+        .iter()
+        .map(|&child| layout_node(state, ws, child))
+        .collect();
+    let focused_child = node.nodes.get(container.focused_child).map(|n| n.id);
+    node.set_focus(focused_child);
+    node
+}
+
+fn window_node(state: &State, ws: &Workspace, id: WindowId) -> TreeNode {
+    let window = state.windows.get(&id);
+    let rect = ws
+        .window_geometry(id)
+        .or_else(|| window.map(|w| w.geometry))
+        .unwrap_or_default();
+
+    let mut node = TreeNode::new(
+        id.0,
+        window.map(|w| w.title.clone()),
+        "con",
+        Rect::from(rect),
+    );
+    node.app_id = window.map(|w| w.app_id.clone());
+    node.pid = window.and_then(|w| w.pid);
+    node.focused =
+        state.focus.focused_container.is_none() && state.focus.focused_window == Some(id);
+    if let Some(window) = window {
+        set_window_fields(&mut node, state, ws, window);
+    }
+    node
+}
+
+/// Border, decoration and state fields of a window's node.
+fn set_window_fields(node: &mut TreeNode, state: &State, ws: &Workspace, window: &Window) {
+    let frame = Geometry::new(node.rect.x, node.rect.y, node.rect.width, node.rect.height);
+    let width = window.border_width();
+    let title_bar = ws.title_bar_height(window, &state.config);
+    let client = ws
+        .window_borders(window.id, width, &state.config)
+        .client_area(frame, title_bar);
+
+    node.border = match window.border {
+        BorderStyle::Normal => "normal",
+        BorderStyle::Pixel(_) => "pixel",
+        BorderStyle::None => "none",
+    }
+    .to_string();
+    node.current_border_width = width as i32;
+    node.window_rect = Rect {
+        x: client.x - frame.x,
+        y: client.y - frame.y,
+        ..Rect::from(client)
+    };
+    node.deco_rect = Rect {
+        width: frame.width,
+        height: title_bar,
+        ..Rect::default()
+    };
+    node.geometry = Rect::from(window.geometry);
+    node.urgent = window.state.contains(WindowState::URGENT);
+    node.sticky = window.state.contains(WindowState::STICKY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::window::Window;
+
+    #[test]
+    fn test_tree_reports_focused_container() {
+        let mut state = State::new(Config::default());
+        state.focus.focused_workspace = state.workspaces.keys().next().copied();
+        let w1 = state.add_window(Window::new(WindowId(1), "a".into(), "A".into()));
+        let w2 = state.add_window(Window::new(WindowId(2), "b".into(), "B".into()));
+        state.focus_window(w2);
+
+        let tree = get_tree(&state);
+        let focused = tree.find_focused().unwrap();
+        assert_eq!(focused.id, w2.0);
+        assert_eq!(focused.border, "pixel");
+        assert_eq!(focused.current_border_width, 2);
+        assert_eq!((focused.window_rect.x, focused.window_rect.y), (2, 2));
+
+        // Each level lists its focused child first.
+        let workspace = &tree.nodes[0];
+        assert_eq!(tree.focus[0], workspace.id);
+        let container = &workspace.nodes[0];
+        assert_eq!(workspace.focus, [container.id]);
+        assert_eq!(container.focus, [w2.0, w1.0]);
+
+        let root = state.focused_workspace().unwrap().layout.root.unwrap();
+        state.focus.focused_container = Some(root);
+        let tree = get_tree(&state);
+        let focused = tree.find_focused().unwrap();
+        assert_eq!(focused.id, root.0);
+        assert_eq!(focused.nodes.len(), 2);
+        assert_eq!(focused.nodes[0].id, w1.0);
+    }
+
+    #[test]
+    fn test_workspace_event_reports_current() {
+        let mut state = State::new(Config::default());
+        let ws = state.workspace_named("mail");
+        let event = workspace_event(&state, "rename", ws);
+        assert_eq!(event.change, "rename");
+        let current = event.current.unwrap();
+        assert_eq!(current.node_type, "workspace");
+        assert_eq!(current.name.as_deref(), Some("mail"));
+        assert!(event.old.is_none());
+
+        let renamed = CoreAction::WorkspaceRenamed {
+            id: ws,
+            old_name: "1".to_string(),
+            name: "mail".to_string(),
+        };
+        let json = serde_json::to_value(action_event(&state, &renamed).unwrap()).unwrap();
+        assert_eq!(json["change"], "rename");
+        assert_eq!(json["current"]["name"], "mail");
+        assert!(action_event(&state, &CoreAction::Exit).is_none());
+    }
+}
@@ -3,20 +3,61 @@
 //! Tree-based layout inspired by i3, with tabbed containers from Fluxbox.
 
 use std::collections::HashMap;
+use std::mem;
+use std::ops::{Index, Range};
 
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
+use crate::config::{Config, FocusWrapping, NewWindowPlacement};
 use crate::state::Geometry;
-use crate::window::WindowId;
+use crate::window::{SizeHints, Window, WindowCriteria, WindowId};
+
+/// Smallest share of a split container a child can be resized to.
+const MIN_RATIO: f64 = 0.05;
+
+/// Bits of a [`ContainerId`] holding the slot number plus one.
+const SLOT_BITS: u32 = 20;
+/// Bits of a [`ContainerId`] holding the slot's generation.
+const GENERATION_BITS: u32 = 12;
+const SLOT_MASK: u64 = (1 << SLOT_BITS) - 1;
+const GENERATION_MASK: u16 = (1 << GENERATION_BITS) - 1;
 
 /// Unique identifier for a layout container.
-pub type ContainerId = u64;
+///
+/// Each tree numbers its containers itself, in the order their slots in
+/// its arena are first used. The top 32 bits hold the tree's ID, the next
+/// 12 the generation of the slot (bumped each time it is freed, so a stale
+/// ID does not find the slot's next container) and the low 20 bits the
+/// slot number plus one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ContainerId(pub u64);
+
+impl ContainerId {
+    const fn new(tree: u32, generation: u16, slot: usize) -> Self {
+        Self(
+            (tree as u64) << (GENERATION_BITS + SLOT_BITS)
+                | (generation as u64) << SLOT_BITS
+                | (slot as u64 + 1),
+        )
+    }
+
+    const fn tree(self) -> u32 {
+        (self.0 >> (GENERATION_BITS + SLOT_BITS)) as u32
+    }
+
+    const fn generation(self) -> u16 {
+        (self.0 >> SLOT_BITS) as u16 & GENERATION_MASK
+    }
 
-static NEXT_CONTAINER_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+    const fn slot(self) -> Option<usize> {
+        ((self.0 & SLOT_MASK) as usize).checked_sub(1)
+    }
+}
 
-fn next_container_id() -> ContainerId {
-    NEXT_CONTAINER_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+impl std::fmt::Display for ContainerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "con:{}", self.0)
+    }
 }
 
 /// Direction for splits.
@@ -45,41 +86,71 @@ pub enum LayoutMode {
     Split,
     Tabbed,
     Stacked,
+    /// dwm-style master area plus a stack of the remaining children.
+    #[serde(rename = "master_stack")]
+    MasterStack,
+    /// Each child halves the space left by the previous one.
+    Dwindle,
+    /// Near-square rows and columns.
+    Grid,
+}
+
+impl LayoutMode {
+    /// Whether the layout places children itself rather than along an axis.
+    #[must_use]
+    pub const fn is_automatic(self) -> bool {
+        matches!(self, Self::MasterStack | Self::Dwindle | Self::Grid)
+    }
 }
 
 /// A node in the layout tree.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LayoutNode {
     Container(ContainerId),
     Window(WindowId),
 }
 
 /// A container in the layout tree.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Container {
+    /// Assigned when the container is added to a tree.
     pub id: ContainerId,
     pub parent: Option<ContainerId>,
     pub children: Vec<LayoutNode>,
     pub layout: LayoutMode,
     pub split_direction: SplitDirection,
     pub ratios: Vec<f64>,
+    /// Whether each child's ratio was set with `resize set`. Pinned shares
+    /// are kept when siblings are added or removed.
+    pub pinned: Vec<bool>,
     pub focused_child: usize,
     pub geometry: Geometry,
     pub gap: u32,
+    /// Share of the container given to the master area in `MasterStack`.
+    pub master_ratio: f64,
+    /// Number of children kept in the master area in `MasterStack`.
+    pub master_count: usize,
+    /// Criteria of the window this empty container is holding a place for
+    /// (see [`crate::saved_layout`]).
+    pub swallows: Option<WindowCriteria>,
 }
 
 impl Container {
     pub fn new(layout: LayoutMode, split_direction: SplitDirection) -> Self {
         Self {
-            id: next_container_id(),
+            id: ContainerId::default(),
             parent: None,
             children: Vec::new(),
             layout,
             split_direction,
             ratios: Vec::new(),
+            pinned: Vec::new(),
             focused_child: 0,
             geometry: Geometry::default(),
             gap: 4,
+            master_ratio: 0.55,
+            master_count: 1,
+            swallows: None,
         }
     }
 
@@ -88,20 +159,54 @@ impl Container {
     }
 
     pub fn add_child(&mut self, node: LayoutNode) {
-        self.children.push(node);
-        self.recalculate_ratios();
+        self.insert_child(self.children.len(), node);
     }
 
+    /// Insert `node` at `index` with an even share of the space not pinned
+    /// by its siblings, taken from the unpinned ones in proportion to their
+    /// ratios.
+    #[allow(clippy::cast_precision_loss)]
     pub fn insert_child(&mut self, index: usize, node: LayoutNode) {
+        self.swallows = None;
         let index = index.min(self.children.len());
         self.children.insert(index, node);
-        self.recalculate_ratios();
+        if self.ratios.len() + 1 == self.children.len() && self.pinned.len() == self.ratios.len() {
+            let pinned = self.pinned.iter().filter(|&&pinned| pinned).count();
+            let free = 1.0
+                - self
+                    .ratios
+                    .iter()
+                    .zip(&self.pinned)
+                    .filter(|(_, &pinned)| pinned)
+                    .map(|(ratio, _)| ratio)
+                    .sum::<f64>();
+            let share = if free >= MIN_RATIO {
+                free / (self.children.len() - pinned) as f64
+            } else {
+                1.0 / self.children.len() as f64
+            };
+            self.ratios.insert(index, share);
+            self.pinned.insert(index, false);
+            self.rescale_ratios(Some(index), 1.0 - share);
+        } else {
+            self.recalculate_ratios();
+        }
     }
 
+    /// Remove the child at `index`, handing its share back to the others
+    /// in proportion to their ratios.
     pub fn remove_child(&mut self, index: usize) -> Option<LayoutNode> {
         if index < self.children.len() {
             let node = self.children.remove(index);
-            self.recalculate_ratios();
+            if self.ratios.len() == self.children.len() + 1
+                && self.pinned.len() == self.ratios.len()
+            {
+                self.ratios.remove(index);
+                self.pinned.remove(index);
+                self.rescale_ratios(None, 1.0);
+            } else {
+                self.recalculate_ratios();
+            }
             if self.focused_child >= self.children.len() && !self.children.is_empty() {
                 self.focused_child = self.children.len() - 1;
             }
@@ -111,17 +216,18 @@ impl Container {
         }
     }
 
-    pub fn remove_node(&mut self, node: &LayoutNode) -> bool {
-        if let Some(pos) = self.children.iter().position(|n| match (n, node) {
-            (LayoutNode::Container(a), LayoutNode::Container(b)) => a == b,
-            (LayoutNode::Window(a), LayoutNode::Window(b)) => a == b,
-            _ => false,
-        }) {
-            self.remove_child(pos);
-            true
-        } else {
-            false
-        }
+    pub fn remove_node(&mut self, node: LayoutNode) -> bool {
+        let Some(pos) = self.position(node) else {
+            return false;
+        };
+        self.remove_child(pos);
+        true
+    }
+
+    /// Index of `node` among this container's children.
+    #[must_use]
+    pub fn position(&self, node: LayoutNode) -> Option<usize> {
+        self.children.iter().position(|n| *n == node)
     }
 
     fn recalculate_ratios(&mut self) {
@@ -132,21 +238,74 @@ impl Container {
         } else {
             self.ratios.clear();
         }
+        self.pinned = vec![false; n];
+    }
+
+    /// Scale the ratios of every child but `keep` so they add up to
+    /// `total`. Only unpinned children change, unless the pinned ones
+    /// alone exceed `total` or none are left to absorb the difference.
+    fn rescale_ratios(&mut self, keep: Option<usize>, total: f64) {
+        let indices: Vec<usize> = (0..self.ratios.len())
+            .filter(|&i| Some(i) != keep)
+            .collect();
+        let pinned: f64 = indices
+            .iter()
+            .filter(|&&i| self.pinned[i])
+            .map(|&i| self.ratios[i])
+            .sum();
+        let flexible: f64 = indices
+            .iter()
+            .filter(|&&i| !self.pinned[i])
+            .map(|&i| self.ratios[i])
+            .sum();
+
+        if flexible > 0.0 && pinned < total {
+            let scale = (total - pinned) / flexible;
+            for i in indices.into_iter().filter(|&i| !self.pinned[i]) {
+                self.ratios[i] *= scale;
+            }
+        } else if pinned + flexible > 0.0 {
+            let scale = total / (pinned + flexible);
+            for i in indices {
+                self.ratios[i] *= scale;
+            }
+        }
+    }
+
+    /// Pin child `index` to `ratio` of the container (`resize set`),
+    /// resizing its unpinned siblings to fit.
+    pub fn pin_ratio(&mut self, index: usize, ratio: f64) {
+        if self.children.len() < 2 || index >= self.ratios.len() {
+            return;
+        }
+        let ratio = ratio.clamp(MIN_RATIO, 1.0 - MIN_RATIO);
+        self.ratios[index] = ratio;
+        self.pinned[index] = true;
+        self.rescale_ratios(Some(index), 1.0 - ratio);
     }
 
     pub fn resize_child(&mut self, index: usize, delta: f64) {
         if self.children.len() < 2 || index >= self.children.len() - 1 {
             return;
         }
-        let min_ratio = 0.05;
         let max_delta =
-            (self.ratios[index + 1] - min_ratio).min(1.0 - min_ratio - self.ratios[index]);
-        let min_delta = -(self.ratios[index] - min_ratio);
+            (self.ratios[index + 1] - MIN_RATIO).min(1.0 - MIN_RATIO - self.ratios[index]);
+        let min_delta = -(self.ratios[index] - MIN_RATIO);
         let clamped_delta = delta.clamp(min_delta, max_delta);
         self.ratios[index] += clamped_delta;
         self.ratios[index + 1] -= clamped_delta;
     }
 
+    /// Grow child `index` by `delta` at the expense of its adjacent sibling
+    /// `neighbour` (or shrink it in the neighbour's favour when negative).
+    pub fn resize_towards(&mut self, index: usize, neighbour: usize, delta: f64) {
+        if neighbour > index {
+            self.resize_child(index, delta);
+        } else {
+            self.resize_child(neighbour, -delta);
+        }
+    }
+
     pub fn focus_next(&mut self) {
         if !self.children.is_empty() {
             self.focused_child = (self.focused_child + 1) % self.children.len();
@@ -171,21 +330,302 @@ impl Container {
         self.children.is_empty()
     }
 
+    /// An empty container waiting to swallow a window matching `swallows`.
+    #[must_use]
+    pub fn is_placeholder(&self) -> bool {
+        self.swallows.is_some() && self.children.is_empty()
+    }
+
     pub fn contains_window(&self, window_id: WindowId) -> bool {
         self.children
             .iter()
             .any(|node| matches!(node, LayoutNode::Window(id) if *id == window_id))
     }
+
+    /// Whether children are arranged along the axis of `direction`.
+    ///
+    /// Tabbed containers count as horizontal and stacked ones as vertical,
+    /// matching how their headers are laid out. Automatic layouts
+    /// (master–stack, dwindle, grid) count along either axis; their
+    /// neighbours are found on screen.
+    #[must_use]
+    pub fn lays_out_along(&self, direction: Direction) -> bool {
+        let horizontal = match self.layout {
+            LayoutMode::Split => self.split_direction == SplitDirection::Horizontal,
+            LayoutMode::Tabbed => true,
+            LayoutMode::Stacked => false,
+            LayoutMode::MasterStack | LayoutMode::Dwindle | LayoutMode::Grid => return true,
+        };
+        horizontal == direction.is_horizontal()
+    }
+
+    /// Switch to a split layout along `direction`.
+    pub fn set_split(&mut self, direction: SplitDirection) {
+        self.layout = LayoutMode::Split;
+        self.split_direction = direction;
+    }
+
+    /// i3 `layout toggle split`: flip the split direction, or return to
+    /// the last split layout from tabbed/stacked.
+    pub fn toggle_split_layout(&mut self) {
+        if self.layout == LayoutMode::Split {
+            self.split_direction = self.split_direction.toggle();
+        } else {
+            self.layout = LayoutMode::Split;
+        }
+    }
+
+    /// i3 `layout toggle`: cycle stacked → tabbed → last split → stacked.
+    pub fn cycle_layout(&mut self) {
+        self.layout = match self.layout {
+            LayoutMode::Stacked => LayoutMode::Tabbed,
+            LayoutMode::Tabbed => LayoutMode::Split,
+            LayoutMode::Split
+            | LayoutMode::MasterStack
+            | LayoutMode::Dwindle
+            | LayoutMode::Grid => LayoutMode::Stacked,
+        };
+    }
+
+    /// i3 `layout toggle all`: cycle stacked → tabbed → splith → splitv →
+    /// stacked.
+    pub fn cycle_all_layouts(&mut self) {
+        match (self.layout, self.split_direction) {
+            (LayoutMode::Stacked, _) => self.layout = LayoutMode::Tabbed,
+            (LayoutMode::Tabbed, _) => self.set_split(SplitDirection::Horizontal),
+            (LayoutMode::Split, SplitDirection::Horizontal) => {
+                self.split_direction = SplitDirection::Vertical;
+            }
+            (LayoutMode::Split, SplitDirection::Vertical)
+            | (LayoutMode::MasterStack | LayoutMode::Dwindle | LayoutMode::Grid, _) => {
+                self.layout = LayoutMode::Stacked;
+            }
+        }
+    }
+
+    /// Set the master area's share, keeping both areas usable.
+    pub fn set_master_ratio(&mut self, ratio: f64) {
+        self.master_ratio = ratio.clamp(0.05, 0.95);
+    }
+}
+
+/// A container taken out of a tree together with everything below it.
+#[derive(Debug)]
+pub struct Subtree {
+    pub root: ContainerId,
+    pub containers: Vec<Container>,
+    pub windows: Vec<WindowId>,
+}
+
+/// The containers of one tree, kept in slots that are reused as
+/// containers come and go.
+///
+/// Borrowing a container mutably marks it changed, so the next layout
+/// pass knows which subtrees to lay out again.
+#[derive(Debug, Default)]
+pub struct ContainerArena {
+    tree: u32,
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    generation: u16,
+    container: Option<Container>,
+    /// Borrowed mutably since the last layout pass.
+    changed: bool,
+    /// Number of the last layout pass that reached the container.
+    laid_out: u32,
+}
+
+impl ContainerArena {
+    fn new(tree: u32) -> Self {
+        Self {
+            tree,
+            ..Self::default()
+        }
+    }
+
+    /// An empty arena for the same tree whose slots carry on from this
+    /// one's generations, so that none of this arena's IDs finds a
+    /// container in it.
+    #[must_use]
+    pub fn successor(&self) -> Self {
+        let slots = self
+            .slots
+            .iter()
+            .map(|slot| Slot {
+                generation: next_generation(slot.generation),
+                ..Slot::default()
+            })
+            .collect();
+        Self {
+            tree: self.tree,
+            slots,
+            free: (0..self.slots.len()).rev().collect(),
+            len: 0,
+        }
+    }
+
+    fn index(&self, id: ContainerId) -> Option<usize> {
+        let index = id.slot()?;
+        let slot = self.slots.get(index)?;
+        (id.tree() == self.tree && slot.generation == id.generation() && slot.container.is_some())
+            .then_some(index)
+    }
+
+    fn slot_mut(&mut self, id: ContainerId) -> Option<&mut Slot> {
+        let index = self.index(id)?;
+        Some(&mut self.slots[index])
+    }
+
+    #[must_use]
+    pub fn get(&self, id: ContainerId) -> Option<&Container> {
+        self.slots[self.index(id)?].container.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: ContainerId) -> Option<&mut Container> {
+        let slot = self.slot_mut(id)?;
+        slot.changed = true;
+        slot.container.as_mut()
+    }
+
+    #[must_use]
+    pub fn contains_key(&self, id: ContainerId) -> bool {
+        self.index(id).is_some()
+    }
+
+    /// Add `container` under the next free ID, which is returned.
+    ///
+    /// # Panics
+    ///
+    /// If the arena already holds as many containers as an ID can number.
+    pub fn insert(&mut self, mut container: Container) -> ContainerId {
+        let index = self.free.pop().unwrap_or_else(|| {
+            assert!(
+                (self.slots.len() as u64) < SLOT_MASK,
+                "layout tree is out of container slots"
+            );
+            self.slots.push(Slot::default());
+            self.slots.len() - 1
+        });
+        let slot = &mut self.slots[index];
+        let id = ContainerId::new(self.tree, slot.generation, index);
+        container.id = id;
+        slot.container = Some(container);
+        slot.changed = true;
+        self.len += 1;
+        id
+    }
+
+    pub fn remove(&mut self, id: ContainerId) -> Option<Container> {
+        let index = self.index(id)?;
+        let slot = &mut self.slots[index];
+        slot.generation = next_generation(slot.generation);
+        slot.changed = false;
+        self.free.push(index);
+        self.len -= 1;
+        slot.container.take()
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Container> {
+        self.slots.iter().filter_map(|slot| slot.container.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Container> {
+        self.slots.iter_mut().filter_map(|slot| {
+            slot.changed |= slot.container.is_some();
+            slot.container.as_mut()
+        })
+    }
+
+    pub fn into_values(self) -> impl Iterator<Item = Container> {
+        self.slots.into_iter().filter_map(|slot| slot.container)
+    }
+
+    fn is_changed(&self, id: ContainerId) -> bool {
+        self.index(id).is_some_and(|index| self.slots[index].changed)
+    }
+
+    fn mark_changed(&mut self, id: ContainerId) {
+        if let Some(slot) = self.slot_mut(id) {
+            slot.changed = true;
+        }
+    }
+}
+
+const fn next_generation(generation: u16) -> u16 {
+    generation.wrapping_add(1) & GENERATION_MASK
+}
+
+impl Index<ContainerId> for ContainerArena {
+    type Output = Container;
+
+    fn index(&self, id: ContainerId) -> &Container {
+        self.get(id).expect("no container with this ID in the tree")
+    }
 }
 
 /// The layout tree for a workspace.
 #[derive(Debug)]
 pub struct LayoutTree {
-    pub containers: HashMap<ContainerId, Container>,
+    pub containers: ContainerArena,
     pub root: Option<ContainerId>,
     pub focused_container: Option<ContainerId>,
     pub default_direction: SplitDirection,
+    /// Root layout overriding `general.default_layout`, e.g. from the
+    /// workspace's config entry.
+    pub default_layout: Option<LayoutMode>,
     pub window_geometries: HashMap<WindowId, Geometry>,
+    /// The container directly holding each window.
+    parents: HashMap<WindowId, ContainerId>,
+    /// Size constraints of the windows that have any.
+    size_hints: HashMap<WindowId, SizeHints>,
+    /// Windows the last layout pass had to give less than their minimum
+    /// size.
+    pub unfit_windows: Vec<WindowId>,
+    /// Height of one tab or stack header row.
+    pub header_height: u32,
+    /// Tabbed and stacked containers the last layout pass drew headers
+    /// for, outermost first.
+    pub tab_containers: Vec<ContainerId>,
+    /// Number of the current layout pass.
+    pass: u32,
+    last_pass: LastPass,
+    scratch: Scratch,
+}
+
+/// What the previous layout pass produced, reused for the subtrees that
+/// have not changed since.
+#[derive(Debug, Default)]
+struct LastPass {
+    header_height: u32,
+    window_geometries: HashMap<WindowId, Geometry>,
+    unfit_windows: Vec<WindowId>,
+    tab_containers: Vec<ContainerId>,
+}
+
+/// Buffers the layout pass reuses rather than allocating. Split lengths
+/// are stacked: each split pushes its children's lengths and pops them
+/// once they are laid out.
+#[derive(Debug, Default)]
+struct Scratch {
+    lengths: Vec<u32>,
+    extents: Vec<Extent>,
+    exact: Vec<f64>,
+    pinned: Vec<bool>,
 }
 
 impl Default for LayoutTree {
@@ -196,226 +636,1584 @@ impl Default for LayoutTree {
 
 impl LayoutTree {
     pub fn new() -> Self {
+        Self::with_id(0)
+    }
+
+    /// An empty tree numbering its containers under `tree`. Trees whose
+    /// container IDs must not clash need different IDs; each workspace
+    /// uses its own.
+    #[must_use]
+    pub fn with_id(tree: u32) -> Self {
         Self {
-            containers: HashMap::new(),
+            containers: ContainerArena::new(tree),
             root: None,
             focused_container: None,
             default_direction: SplitDirection::Horizontal,
+            default_layout: None,
             window_geometries: HashMap::new(),
+            parents: HashMap::new(),
+            size_hints: HashMap::new(),
+            unfit_windows: Vec::new(),
+            header_height: 24,
+            tab_containers: Vec::new(),
+            pass: 0,
+            last_pass: LastPass::default(),
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// An empty tree to replace this one, numbering its containers so
+    /// that no ID handed out by this tree finds one of them.
+    #[must_use]
+    pub fn successor(&self) -> Self {
+        Self {
+            containers: self.containers.successor(),
+            ..Self::with_id(self.containers.tree)
         }
     }
 
     pub fn add_window(&mut self, window_id: WindowId, config: &Config) {
+        self.place_window(window_id, config.general.new_window_placement, config);
+    }
+
+    /// Add `window_id` to the tree at `placement`, relative to the focused
+    /// window, and focus it.
+    pub fn place_window(
+        &mut self,
+        window_id: WindowId,
+        placement: NewWindowPlacement,
+        config: &Config,
+    ) {
         let node = LayoutNode::Window(window_id);
 
-        if self.root.is_none() {
-            let mut root = Container::new_split(self.default_direction);
+        let Some(root_id) = self.root else {
+            let layout = self.default_layout.unwrap_or(config.general.default_layout);
+            let mut root = Container::new(layout, self.default_direction);
             root.gap = config.gaps.inner;
+            root.set_master_ratio(config.general.master_ratio);
+            root.master_count = config.general.master_count;
             root.add_child(node);
-            let root_id = root.id;
-            self.containers.insert(root_id, root);
+            let root_id = self.containers.insert(root);
+            self.parents.insert(window_id, root_id);
             self.root = Some(root_id);
             self.focused_container = Some(root_id);
             return;
+        };
+
+        match placement {
+            NewWindowPlacement::AfterFocused if config.general.autotiling => {
+                self.split_focused(false);
+            }
+            NewWindowPlacement::SplitFocused => self.split_focused(true),
+            _ => {}
+        }
+
+        let focused_id = self.focused_container.unwrap_or(root_id);
+        let (target_id, index) = match placement {
+            NewWindowPlacement::AfterFocused | NewWindowPlacement::SplitFocused => {
+                let after = self
+                    .containers
+                    .get(focused_id)
+                    .map_or(0, |c| c.focused_child + 1);
+                (focused_id, after)
+            }
+            NewWindowPlacement::End => {
+                let len = self
+                    .containers
+                    .get(root_id)
+                    .map_or(0, |c| c.children.len());
+                (root_id, len)
+            }
+            NewWindowPlacement::Start => (root_id, 0),
+            NewWindowPlacement::AsMaster => (focused_id, 0),
+        };
+        if let Some(container) = self.containers.get_mut(target_id) {
+            container.insert_child(index, node);
+            self.parents.insert(window_id, target_id);
         }
+        self.focus_window(window_id);
+    }
 
-        let target_id = self.focused_container.or(self.root).unwrap();
-        if let Some(container) = self.containers.get_mut(&target_id) {
-            let insert_pos = container.focused_child + 1;
-            container.insert_child(insert_pos, node);
-            container.focused_child = insert_pos;
+    /// Split the focused window along its longer side so the next window
+    /// opens beside or below it. Unless `always`, a window whose container
+    /// already runs that way is left as it is (`autotiling`).
+    fn split_focused(&mut self, always: bool) {
+        let Some(window_id) = self.focused_window() else {
+            return;
+        };
+        let direction = match self.window_geometries.get(&window_id) {
+            Some(geometry) if geometry.height > geometry.width => SplitDirection::Vertical,
+            Some(_) => SplitDirection::Horizontal,
+            None if always => self.default_direction,
+            None => return,
+        };
+        let unchanged = self
+            .container_of(window_id)
+            .and_then(|id| self.containers.get(id))
+            .is_some_and(|c| c.layout != LayoutMode::Split || c.split_direction == direction);
+        if always || !unchanged {
+            self.split(window_id, direction);
         }
     }
 
     pub fn remove_window(&mut self, window_id: WindowId) -> bool {
-        let node = LayoutNode::Window(window_id);
-        let mut container_to_remove = None;
+        let Some(container_id) = self.parents.remove(&window_id) else {
+            return false;
+        };
 
-        for (container_id, container) in self.containers.iter_mut() {
-            if container.remove_node(&node) {
-                if container.is_empty() {
-                    container_to_remove = Some(*container_id);
-                }
-                break;
-            }
-        }
+        let now_empty = self
+            .containers
+            .get_mut(container_id)
+            .is_some_and(|container| {
+                container.remove_node(LayoutNode::Window(window_id));
+                container.is_empty()
+            });
+        let survivor = if now_empty {
+            self.remove_empty_container(container_id)
+        } else {
+            Some(container_id)
+        };
 
-        if let Some(empty_id) = container_to_remove {
-            self.remove_empty_container(empty_id);
+        if self
+            .focused_container
+            .map_or(true, |id| !self.containers.contains_key(id))
+        {
+            self.focused_container = survivor.map(|id| self.focused_leaf_container(id));
         }
 
         self.window_geometries.remove(&window_id);
+        self.size_hints.remove(&window_id);
         true
     }
 
-    fn remove_empty_container(&mut self, container_id: ContainerId) {
-        let container = match self.containers.remove(&container_id) {
-            Some(c) => c,
-            None => return,
-        };
+    /// Remove an empty container and any ancestors it leaves empty.
+    ///
+    /// Returns the nearest ancestor that still has children, if any.
+    fn remove_empty_container(&mut self, container_id: ContainerId) -> Option<ContainerId> {
+        let container = self.containers.remove(container_id)?;
 
         if self.root == Some(container_id) {
             self.root = None;
             self.focused_container = None;
-            return;
+            return None;
         }
 
-        if let Some(parent_id) = container.parent {
-            if let Some(parent) = self.containers.get_mut(&parent_id) {
-                parent.remove_node(&LayoutNode::Container(container_id));
-            }
+        let parent_id = container.parent?;
+        let parent = self.containers.get_mut(parent_id)?;
+        parent.remove_node(LayoutNode::Container(container_id));
+        if parent.is_empty() {
+            self.remove_empty_container(parent_id)
+        } else {
+            Some(parent_id)
         }
     }
 
-    pub fn calculate_layout(&mut self, available: Geometry, outer_gap: u32) {
-        self.window_geometries.clear();
+    /// Find the container that directly holds `window_id`.
+    #[must_use]
+    pub fn container_of(&self, window_id: WindowId) -> Option<ContainerId> {
+        self.parents.get(&window_id).copied()
+    }
 
-        if let Some(root_id) = self.root {
-            let inner = Geometry::new(
-                available.x + outer_gap as i32,
-                available.y + outer_gap as i32,
-                available.width.saturating_sub(outer_gap * 2),
-                available.height.saturating_sub(outer_gap * 2),
-            );
-            self.layout_container(root_id, inner);
+    /// Record `container_id` as the parent of `node`, which it now holds.
+    fn adopt(&mut self, container_id: ContainerId, node: LayoutNode) {
+        match node {
+            LayoutNode::Window(id) => {
+                self.parents.insert(id, container_id);
+            }
+            LayoutNode::Container(id) => {
+                if let Some(child) = self.containers.get_mut(id) {
+                    child.parent = Some(container_id);
+                }
+            }
         }
     }
 
-    fn layout_container(&mut self, container_id: ContainerId, geometry: Geometry) {
-        let container = match self.containers.get(&container_id).cloned() {
-            Some(c) => c,
-            None => return,
+    /// Whether the window index agrees with the containers' children.
+    #[must_use]
+    pub fn index_is_consistent(&self) -> bool {
+        let mut windows = 0;
+        for container in self.containers.values() {
+            for child in &container.children {
+                if let LayoutNode::Window(id) = child {
+                    windows += 1;
+                    if self.parents.get(id) != Some(&container.id) {
+                        return false;
+                    }
+                }
+            }
+        }
+        windows == self.parents.len()
+    }
+
+    /// Make `window_id` the focused leaf, updating `focused_child` on every
+    /// container between it and the root.
+    pub fn focus_window(&mut self, window_id: WindowId) -> bool {
+        let Some(container_id) = self.container_of(window_id) else {
+            return false;
         };
+        self.focused_container = Some(container_id);
+        self.focus_path(container_id, LayoutNode::Window(window_id));
+        true
+    }
 
-        if let Some(c) = self.containers.get_mut(&container_id) {
-            c.geometry = geometry;
+    /// Point `focused_child` at `node` in `container_id` and at each
+    /// ancestor's child on the way up.
+    fn focus_path(&mut self, container_id: ContainerId, node: LayoutNode) {
+        let mut current = Some((container_id, node));
+        while let Some((id, node)) = current {
+            let Some(container) = self.containers.get(id) else {
+                break;
+            };
+            let position = container.position(node);
+            let parent = container.parent;
+            // Only a container whose focus moves needs laying out again.
+            if let Some(pos) = position.filter(|&pos| pos != container.focused_child) {
+                if let Some(container) = self.containers.get_mut(id) {
+                    container.focused_child = pos;
+                }
+            }
+            current = parent.map(|parent| (parent, LayoutNode::Container(id)));
         }
+    }
 
-        if container.children.is_empty() {
-            return;
+    /// The window that currently holds focus inside this tree.
+    #[must_use]
+    pub fn focused_window(&self) -> Option<WindowId> {
+        let container = self.containers.get(self.focused_container?)?;
+        match container.focused()? {
+            LayoutNode::Window(id) => Some(*id),
+            LayoutNode::Container(id) => self.focused_window_in(*id),
         }
+    }
 
-        match container.layout {
-            LayoutMode::Split => self.layout_split(&container, geometry),
-            LayoutMode::Tabbed | LayoutMode::Stacked => self.layout_tabbed(&container, geometry),
+    /// Follow `focused_child` from `container_id` down to a window.
+    fn focused_window_in(&self, container_id: ContainerId) -> Option<WindowId> {
+        let container = self.containers.get(container_id)?;
+        match container.focused()? {
+            LayoutNode::Window(id) => Some(*id),
+            LayoutNode::Container(id) => self.focused_window_in(*id),
         }
     }
 
-    fn layout_split(&mut self, container: &Container, geometry: Geometry) {
-        let n = container.children.len();
-        if n == 0 {
-            return;
+    /// Follow `focused_child` from `container_id` down to the deepest
+    /// container whose focused child is a window.
+    fn focused_leaf_container(&self, container_id: ContainerId) -> ContainerId {
+        let mut id = container_id;
+        while let Some(LayoutNode::Container(child)) =
+            self.containers.get(id).and_then(Container::focused)
+        {
+            // New windows open beside a placeholder, not inside it.
+            if self
+                .containers
+                .get(*child)
+                .is_some_and(Container::is_placeholder)
+            {
+                break;
+            }
+            id = *child;
         }
+        id
+    }
 
-        let gap = container.gap;
-        let total_gap = gap * (n as u32 - 1);
+    /// Split `window_id` along `direction` (i3 `split h|v`).
+    ///
+    /// The window is wrapped in a new child container so that windows
+    /// opened next land beside it inside the split. When the window is
+    /// already the only child of its container, that container is
+    /// re-oriented instead of nesting another level.
+    pub fn split(&mut self, window_id: WindowId, direction: SplitDirection) -> bool {
+        let Some(container_id) = self.container_of(window_id) else {
+            return false;
+        };
+        self.focus_window(window_id);
 
-        match container.split_direction {
-            SplitDirection::Horizontal => {
-                let available_width = geometry.width.saturating_sub(total_gap);
-                let mut x = geometry.x;
+        let Some(container) = self.containers.get_mut(container_id) else {
+            return false;
+        };
+        if container.children.len() == 1 {
+            container.layout = LayoutMode::Split;
+            container.split_direction = direction;
+            return true;
+        }
 
-                for (i, child) in container.children.iter().enumerate() {
-                    let ratio = container.ratios.get(i).copied().unwrap_or(1.0 / n as f64);
-                    let width = if i == n - 1 {
-                        (geometry.x + geometry.width as i32 - x) as u32
-                    } else {
-                        (available_width as f64 * ratio) as u32
-                    };
-                    let child_geo = Geometry::new(x, geometry.y, width, geometry.height);
-                    self.layout_child(child, child_geo);
-                    x += width as i32 + gap as i32;
-                }
-            }
-            SplitDirection::Vertical => {
-                let available_height = geometry.height.saturating_sub(total_gap);
-                let mut y = geometry.y;
+        let mut split = Container::new_split(direction);
+        split.parent = Some(container_id);
+        split.gap = container.gap;
+        split.add_child(LayoutNode::Window(window_id));
+        let index = container.focused_child;
 
-                for (i, child) in container.children.iter().enumerate() {
-                    let ratio = container.ratios.get(i).copied().unwrap_or(1.0 / n as f64);
-                    let height = if i == n - 1 {
-                        (geometry.y + geometry.height as i32 - y) as u32
-                    } else {
-                        (available_height as f64 * ratio) as u32
-                    };
-                    let child_geo = Geometry::new(geometry.x, y, geometry.width, height);
-                    self.layout_child(child, child_geo);
-                    y += height as i32 + gap as i32;
-                }
-            }
+        let split_id = self.containers.insert(split);
+        if let Some(container) = self.containers.get_mut(container_id) {
+            container.children[index] = LayoutNode::Container(split_id);
         }
+        self.parents.insert(window_id, split_id);
+        self.focused_container = Some(split_id);
+        true
     }
 
-    fn layout_tabbed(&mut self, container: &Container, geometry: Geometry) {
-        let tab_height = 24u32;
-
-        let content_geo = match container.layout {
-            LayoutMode::Tabbed => Geometry::new(
-                geometry.x,
-                geometry.y + tab_height as i32,
-                geometry.width,
-                geometry.height.saturating_sub(tab_height),
-            ),
-            LayoutMode::Stacked => {
-                let header_height = tab_height * container.children.len() as u32;
-                Geometry::new(
-                    geometry.x,
-                    geometry.y + header_height as i32,
-                    geometry.width,
-                    geometry.height.saturating_sub(header_height),
-                )
-            }
-            _ => geometry,
+    /// Undo a split around `window_id` (i3 `split none`).
+    ///
+    /// Only applies when the window is the sole child of a nested
+    /// container; the container is replaced by the window in its parent.
+    pub fn unsplit(&mut self, window_id: WindowId) -> bool {
+        let Some(container_id) = self.container_of(window_id) else {
+            return false;
+        };
+        let Some(container) = self.containers.get(container_id) else {
+            return false;
         };
+        let Some(parent_id) = container.parent else {
+            return false;
+        };
+        if container.children.len() != 1 {
+            return false;
+        }
 
-        for (i, child) in container.children.iter().enumerate() {
-            if i == container.focused_child {
-                self.layout_child(child, content_geo);
+        self.containers.remove(container_id);
+        if let Some(parent) = self.containers.get_mut(parent_id) {
+            if let Some(pos) = parent.position(LayoutNode::Container(container_id)) {
+                parent.children[pos] = LayoutNode::Window(window_id);
             }
         }
+        self.parents.insert(window_id, parent_id);
+        self.focus_window(window_id)
     }
 
-    fn layout_child(&mut self, child: &LayoutNode, geometry: Geometry) {
-        match child {
-            LayoutNode::Container(id) => self.layout_container(*id, geometry),
-            LayoutNode::Window(id) => {
-                self.window_geometries.insert(*id, geometry);
+    /// Split direction of the container directly holding `window_id`.
+    #[must_use]
+    pub fn direction_of(&self, window_id: WindowId) -> Option<SplitDirection> {
+        let container_id = self.container_of(window_id)?;
+        self.containers
+            .get(container_id)
+            .map(|c| c.split_direction)
+    }
+
+    /// Constrain `window_id` to `hints` in the next layout pass. Default
+    /// hints lift any constraint.
+    pub fn set_size_hints(&mut self, window_id: WindowId, hints: SizeHints) {
+        let changed = if hints == SizeHints::default() {
+            self.size_hints.remove(&window_id).is_some()
+        } else {
+            self.size_hints.insert(window_id, hints) != Some(hints)
+        };
+        if changed {
+            if let Some(container_id) = self.container_of(window_id) {
+                self.containers.mark_changed(container_id);
             }
         }
     }
 
-    pub fn focus_direction(&mut self, direction: Direction) -> Option<WindowId> {
-        let container_id = self.focused_container?;
-        {
-            let container = self.containers.get_mut(&container_id)?;
-            match direction {
-                Direction::Left | Direction::Up => container.focus_prev(),
-                Direction::Right | Direction::Down => container.focus_next(),
+    /// Lay the tree out in `available`. Only subtrees that changed since
+    /// the last pass, or were given a different geometry, are laid out
+    /// again; the rest keep what the last pass produced.
+    pub fn calculate_layout(&mut self, available: Geometry, outer_gap: u32) {
+        let last = &mut self.last_pass;
+        mem::swap(&mut self.window_geometries, &mut last.window_geometries);
+        mem::swap(&mut self.unfit_windows, &mut last.unfit_windows);
+        mem::swap(&mut self.tab_containers, &mut last.tab_containers);
+        self.window_geometries.clear();
+        self.unfit_windows.clear();
+        self.tab_containers.clear();
+        if last.header_height != self.header_height {
+            last.header_height = self.header_height;
+            for slot in &mut self.containers.slots {
+                slot.changed = true;
             }
         }
-        let container = self.containers.get(&container_id)?;
-        match container.focused() {
-            Some(LayoutNode::Window(id)) => Some(*id),
-            Some(LayoutNode::Container(id)) => self.first_window_in_container(*id),
-            None => None,
+        self.pass = self.pass.wrapping_add(1);
+
+        if let Some(root_id) = self.root {
+            self.propagate_changes(root_id);
+            let inner = Geometry::new(
+                available.x + outer_gap as i32,
+                available.y + outer_gap as i32,
+                available.width.saturating_sub(outer_gap * 2),
+                available.height.saturating_sub(outer_gap * 2),
+            );
+            self.layout_container(root_id, inner);
         }
-    }
 
-    fn first_window_in_container(&self, container_id: ContainerId) -> Option<WindowId> {
-        let container = self.containers.get(&container_id)?;
-        match container.children.first()? {
-            LayoutNode::Window(id) => Some(*id),
-            LayoutNode::Container(id) => self.first_window_in_container(*id),
+        for slot in &mut self.containers.slots {
+            slot.changed = false;
         }
     }
 
-    pub fn toggle_split(&mut self) {
-        if let Some(container_id) = self.focused_container {
-            if let Some(container) = self.containers.get_mut(&container_id) {
-                container.split_direction = container.split_direction.toggle();
+    /// Mark `container_id` changed if anything below it is, returning
+    /// whether it is.
+    fn propagate_changes(&mut self, container_id: ContainerId) -> bool {
+        let Some(container) = self.containers.get(container_id) else {
+            return false;
+        };
+        let mut changed = self.containers.is_changed(container_id);
+        for i in 0..container.children.len() {
+            if let LayoutNode::Container(child) = self.containers[container_id].children[i] {
+                changed |= self.propagate_changes(child);
+            }
+        }
+        if changed {
+            self.containers.mark_changed(container_id);
+        }
+        changed
+    }
+
+    /// Repeat what the last pass produced for the unchanged subtree under
+    /// `container_id`.
+    fn reuse_layout(&mut self, container_id: ContainerId) {
+        let Some(container) = self.containers.get(container_id) else {
+            return;
+        };
+        let len = container.children.len();
+        let visible = match container.layout {
+            LayoutMode::Tabbed | LayoutMode::Stacked => {
+                container.focused_child..(container.focused_child + 1).min(len)
+            }
+            _ => 0..len,
+        };
+        if self.last_pass.tab_containers.contains(&container_id) {
+            self.tab_containers.push(container_id);
+        }
+        for i in visible {
+            match self.containers[container_id].children[i] {
+                LayoutNode::Window(id) => {
+                    if let Some(&geometry) = self.last_pass.window_geometries.get(&id) {
+                        self.window_geometries.insert(id, geometry);
+                    }
+                    if self.last_pass.unfit_windows.contains(&id) {
+                        self.unfit_windows.push(id);
+                    }
+                }
+                LayoutNode::Container(child) => {
+                    if let Some(slot) = self.containers.slot_mut(child) {
+                        slot.laid_out = self.pass;
+                    }
+                    self.reuse_layout(child);
+                }
+            }
+        }
+    }
+
+    fn layout_container(&mut self, container_id: ContainerId, geometry: Geometry) {
+        let pass = self.pass;
+        let Some(slot) = self.containers.slot_mut(container_id) else {
+            return;
+        };
+        // A container the last pass did not reach (say, in a hidden tab)
+        // has nothing to reuse.
+        let unchanged = !slot.changed
+            && slot.laid_out == pass.wrapping_sub(1)
+            && slot
+                .container
+                .as_ref()
+                .is_some_and(|c| c.geometry == geometry);
+        slot.laid_out = pass;
+        if unchanged {
+            self.reuse_layout(container_id);
+            return;
+        }
+
+        let Some(container) = slot.container.as_mut() else {
+            return;
+        };
+        container.geometry = geometry;
+        if container.children.is_empty() {
+            return;
+        }
+
+        match container.layout {
+            LayoutMode::Split => self.layout_split(container_id, geometry),
+            LayoutMode::Tabbed | LayoutMode::Stacked => self.layout_tabbed(container_id, geometry),
+            LayoutMode::MasterStack => self.layout_master_stack(container_id, geometry),
+            LayoutMode::Dwindle => self.layout_dwindle(container_id, geometry),
+            LayoutMode::Grid => self.layout_grid(container_id, geometry),
+        }
+    }
+
+    fn layout_split(&mut self, container_id: ContainerId, geometry: Geometry) {
+        let container = &self.containers[container_id];
+        let n = container.children.len();
+        let gap = container.gap;
+        let horizontal = container.split_direction == SplitDirection::Horizontal;
+        let length = if horizontal {
+            geometry.width
+        } else {
+            geometry.height
+        };
+        let available = length.saturating_sub(gap * (n as u32 - 1));
+        let start = self.scratch.lengths.len();
+        self.distribute(container_id, available);
+
+        let mut offset = 0i32;
+        for i in 0..n {
+            let length = self.scratch.lengths[start + i];
+            let child = self.containers[container_id].children[i];
+            let child_geo = if horizontal {
+                Geometry::new(geometry.x + offset, geometry.y, length, geometry.height)
+            } else {
+                Geometry::new(geometry.x, geometry.y + offset, geometry.width, length)
+            };
+            self.layout_child(child, child_geo);
+            offset += length as i32 + gap as i32;
+        }
+        self.scratch.lengths.truncate(start);
+    }
+
+    /// Share `available` between the children of split `container_id`
+    /// along its axis, pushing their lengths onto the scratch stack: in
+    /// proportion to the ratios, but clamped to each child's extent, with
+    /// the shortfall or excess of clamped children spread over the rest.
+    /// Resize increments are then snapped, and the slack goes to the last
+    /// child that can still grow.
+    #[allow(clippy::cast_precision_loss)]
+    fn distribute(&mut self, container_id: ContainerId, available: u32) {
+        let mut extents = mem::take(&mut self.scratch.extents);
+        let mut exact = mem::take(&mut self.scratch.exact);
+        let mut pinned = mem::take(&mut self.scratch.pinned);
+        let mut lengths = mem::take(&mut self.scratch.lengths);
+        let start = lengths.len();
+
+        let container = &self.containers[container_id];
+        let n = container.children.len();
+        let axis = container.split_direction;
+        extents.clear();
+        extents.extend(
+            container
+                .children
+                .iter()
+                .map(|&child| self.extent(child, axis)),
+        );
+
+        let min_total: u64 = extents.iter().map(|e| u64::from(e.min)).sum();
+        if min_total > u64::from(available) {
+            // Not even the minimums fit: shrink them alike. The windows
+            // left too small are reported by `layout_child`.
+            lengths.extend(
+                extents
+                    .iter()
+                    .map(|e| (u64::from(e.min) * u64::from(available) / min_total) as u32),
+            );
+            let used: u32 = lengths[start..].iter().sum();
+            if let Some(last) = lengths.last_mut() {
+                *last += available - used;
+            }
+        } else {
+            let ratio = |i: usize| container.ratios.get(i).copied().unwrap_or(1.0 / n as f64);
+            exact.clear();
+            exact.resize(n, 0.0);
+            pinned.clear();
+            pinned.resize(n, false);
+            loop {
+                let taken: f64 = (0..n).filter(|&i| pinned[i]).map(|i| exact[i]).sum();
+                let weight: f64 = (0..n).filter(|&i| !pinned[i]).map(ratio).sum();
+                if weight <= 0.0 {
+                    break;
+                }
+                let free = f64::from(available) - taken;
+                let mut violation = 0.0;
+                for i in (0..n).filter(|&i| !pinned[i]) {
+                    exact[i] = free * ratio(i) / weight;
+                    violation += extents[i].clamp(exact[i]) - exact[i];
+                }
+                if violation == 0.0 {
+                    break;
+                }
+                // Pin the children on the side that is over-committed and
+                // share what is left between the others.
+                for i in 0..n {
+                    let clamped = extents[i].clamp(exact[i]);
+                    if !pinned[i]
+                        && ((violation > 0.0 && clamped > exact[i])
+                            || (violation < 0.0 && clamped < exact[i]))
+                    {
+                        exact[i] = clamped;
+                        pinned[i] = true;
+                    }
+                }
+            }
+
+            lengths.extend(exact.iter().map(|&length| length as u32));
+            let own = &mut lengths[start..];
+            for (i, &child) in container.children.iter().enumerate() {
+                if let Some((base, increment)) = self.increment(child, axis) {
+                    let snapped = snap(own[i], base, increment);
+                    if snapped >= extents[i].min {
+                        own[i] = snapped;
+                    }
+                }
+            }
+            let slack = available.saturating_sub(own.iter().sum());
+            let flexible = (0..n).rev().find(|&i| {
+                own[i] < extents[i].max && self.increment(container.children[i], axis).is_none()
+            });
+            if let Some(i) = flexible {
+                own[i] += slack.min(extents[i].max - own[i]);
+            }
+        }
+
+        self.scratch = Scratch {
+            lengths,
+            extents,
+            exact,
+            pinned,
+        };
+    }
+
+    /// How far `node` can shrink and grow along `axis`, from the size
+    /// hints of the windows inside it.
+    fn extent(&self, node: LayoutNode, axis: SplitDirection) -> Extent {
+        let container = match node {
+            LayoutNode::Window(id) => {
+                let Some(hints) = self.size_hints.get(&id) else {
+                    return Extent::FLEXIBLE;
+                };
+                let (min, max) = match axis {
+                    SplitDirection::Horizontal => (hints.min_width, hints.max_width),
+                    SplitDirection::Vertical => (hints.min_height, hints.max_height),
+                };
+                let min = min.unwrap_or(0);
+                return Extent {
+                    min,
+                    max: max.unwrap_or(u32::MAX).max(min),
+                };
+            }
+            LayoutNode::Container(id) => match self.containers.get(id) {
+                Some(c) if !c.children.is_empty() => c,
+                _ => return Extent::FLEXIBLE,
+            },
+        };
+
+        let extents = container
+            .children
+            .iter()
+            .map(|&child| self.extent(child, axis));
+        match container.layout {
+            // Side by side along the axis: the children's extents add up.
+            LayoutMode::Split if container.split_direction == axis => {
+                let gaps = container.gap * (container.children.len() as u32 - 1);
+                extents.fold(
+                    Extent {
+                        min: gaps,
+                        max: gaps,
+                    },
+                    |total, e| Extent {
+                        min: total.min.saturating_add(e.min),
+                        max: total.max.saturating_add(e.max),
+                    },
+                )
+            }
+            // Every child spans the whole length.
+            LayoutMode::Split | LayoutMode::Tabbed | LayoutMode::Stacked => {
+                extents.fold(Extent { min: 0, max: 0 }, |total, e| Extent {
+                    min: total.min.max(e.min),
+                    max: total.max.max(e.max),
+                })
+            }
+            _ => Extent {
+                min: extents.map(|e| e.min).max().unwrap_or(0),
+                max: u32::MAX,
+            },
+        }
+    }
+
+    /// Base size and resize increment of window `node` along `axis`.
+    fn increment(&self, node: LayoutNode, axis: SplitDirection) -> Option<(u32, u32)> {
+        let LayoutNode::Window(id) = node else {
+            return None;
+        };
+        let hints = self.size_hints.get(&id)?;
+        let (base, min, increment) = match axis {
+            SplitDirection::Horizontal => {
+                (hints.base_width, hints.min_width, hints.width_increment)
+            }
+            SplitDirection::Vertical => {
+                (hints.base_height, hints.min_height, hints.height_increment)
+            }
+        };
+        let increment = increment.filter(|&increment| increment > 1)?;
+        Some((base.or(min).unwrap_or(0), increment))
+    }
+
+    /// dwm-style tiling: the first `master_count` children share the master
+    /// area and the rest are stacked beside it, to the right for horizontal
+    /// containers and below for vertical ones.
+    fn layout_master_stack(&mut self, container_id: ContainerId, geometry: Geometry) {
+        let container = &self.containers[container_id];
+        let n = container.children.len();
+        let masters = container.master_count.min(n);
+        let gap = container.gap;
+        let direction = container.split_direction;
+
+        let (master_area, stack_area) = if masters == 0 || masters == n {
+            (geometry, geometry)
+        } else {
+            match direction {
+                SplitDirection::Horizontal => {
+                    let available = geometry.width.saturating_sub(gap);
+                    let width = (f64::from(available) * container.master_ratio) as u32;
+                    (
+                        Geometry::new(geometry.x, geometry.y, width, geometry.height),
+                        Geometry::new(
+                            geometry.x + (width + gap) as i32,
+                            geometry.y,
+                            available - width,
+                            geometry.height,
+                        ),
+                    )
+                }
+                SplitDirection::Vertical => {
+                    let available = geometry.height.saturating_sub(gap);
+                    let height = (f64::from(available) * container.master_ratio) as u32;
+                    (
+                        Geometry::new(geometry.x, geometry.y, geometry.width, height),
+                        Geometry::new(
+                            geometry.x,
+                            geometry.y + (height + gap) as i32,
+                            geometry.width,
+                            available - height,
+                        ),
+                    )
+                }
+            }
+        };
+
+        let across = direction.toggle();
+        self.layout_evenly(container_id, 0..masters, master_area, gap, across);
+        self.layout_evenly(container_id, masters..n, stack_area, gap, across);
+    }
+
+    /// Give the children of `container_id` in `range` equal slices of
+    /// `geometry` along `direction`.
+    fn layout_evenly(
+        &mut self,
+        container_id: ContainerId,
+        range: Range<usize>,
+        geometry: Geometry,
+        gap: u32,
+        direction: SplitDirection,
+    ) {
+        let slices = split_evenly(geometry, range.len() as u32, gap, direction);
+        for (i, slice) in range.zip(slices) {
+            let child = self.containers[container_id].children[i];
+            self.layout_child(child, slice);
+        }
+    }
+
+    /// Each child takes half of the space left by the previous one,
+    /// alternating the axis, starting with the container's split direction.
+    fn layout_dwindle(&mut self, container_id: ContainerId, geometry: Geometry) {
+        let container = &self.containers[container_id];
+        let mut remaining = geometry;
+        let mut direction = container.split_direction;
+        let gap = container.gap;
+        let last = container.children.len() - 1;
+
+        for i in 0..=last {
+            let child = self.containers[container_id].children[i];
+            if i == last {
+                self.layout_child(child, remaining);
+                break;
+            }
+            let mut halves = split_evenly(remaining, 2, gap, direction);
+            let (Some(first), Some(second)) = (halves.next(), halves.next()) else {
+                break;
+            };
+            self.layout_child(child, first);
+            remaining = second;
+            direction = direction.toggle();
+        }
+    }
+
+    /// Near-square grid: `ceil(sqrt(n))` columns, filled row by row. A short
+    /// last row stretches its cells across the full width.
+    fn layout_grid(&mut self, container_id: ContainerId, geometry: Geometry) {
+        let container = &self.containers[container_id];
+        let n = container.children.len();
+        let mut columns = 1;
+        while columns * columns < n {
+            columns += 1;
+        }
+        let rows = (n + columns - 1) / columns;
+
+        let gap = container.gap;
+        let row_slices = split_evenly(geometry, rows as u32, gap, SplitDirection::Vertical);
+        for (row, slice) in row_slices.enumerate() {
+            let start = row * columns;
+            let range = start..(start + columns).min(n);
+            self.layout_evenly(container_id, range, slice, gap, SplitDirection::Horizontal);
+        }
+    }
+
+    fn layout_tabbed(&mut self, container_id: ContainerId, geometry: Geometry) {
+        let tab_height = self.header_height;
+        self.tab_containers.push(container_id);
+
+        let container = &self.containers[container_id];
+        let content_geo = match container.layout {
+            LayoutMode::Tabbed => Geometry::new(
+                geometry.x,
+                geometry.y + tab_height as i32,
+                geometry.width,
+                geometry.height.saturating_sub(tab_height),
+            ),
+            LayoutMode::Stacked => {
+                let header_height = tab_height * container.children.len() as u32;
+                Geometry::new(
+                    geometry.x,
+                    geometry.y + header_height as i32,
+                    geometry.width,
+                    geometry.height.saturating_sub(header_height),
+                )
+            }
+            _ => geometry,
+        };
+
+        if let Some(&child) = container.focused() {
+            self.layout_child(child, content_geo);
+        }
+    }
+
+    fn layout_child(&mut self, child: LayoutNode, geometry: Geometry) {
+        match child {
+            LayoutNode::Container(id) => self.layout_container(id, geometry),
+            LayoutNode::Window(id) => {
+                let geometry = self.fit_to_hints(id, geometry);
+                self.window_geometries.insert(id, geometry);
+            }
+        }
+    }
+
+    /// Shrink `geometry` to the maximum size and resize increments of
+    /// window `id`. A window given less than its minimum size keeps the
+    /// geometry and is recorded in `unfit_windows`.
+    fn fit_to_hints(&mut self, id: WindowId, geometry: Geometry) -> Geometry {
+        let Some(hints) = self.size_hints.get(&id) else {
+            return geometry;
+        };
+        if geometry.width < hints.min_width.unwrap_or(0)
+            || geometry.height < hints.min_height.unwrap_or(0)
+        {
+            self.unfit_windows.push(id);
+            return geometry;
+        }
+
+        let node = LayoutNode::Window(id);
+        let fit = |length: u32, axis: SplitDirection| {
+            let extent = self.extent(node, axis);
+            let length = length.min(extent.max);
+            match self.increment(node, axis) {
+                Some((base, increment)) => Some(snap(length, base, increment))
+                    .filter(|&snapped| snapped >= extent.min)
+                    .unwrap_or(length),
+                None => length,
+            }
+        };
+        let width = fit(geometry.width, SplitDirection::Horizontal);
+        let height = fit(geometry.height, SplitDirection::Vertical);
+        Geometry::new(geometry.x, geometry.y, width, height)
+    }
+
+    /// Move focus to the window in `direction` from the focused window.
+    pub fn focus_direction(
+        &mut self,
+        direction: Direction,
+        wrapping: FocusWrapping,
+    ) -> Option<WindowId> {
+        let current = self.focused_window()?;
+        let target = self.neighbour(LayoutNode::Window(current), direction, wrapping)?;
+        self.focus_window(target);
+        Some(target)
+    }
+
+    /// Find the window that lies in `direction` from `node`.
+    ///
+    /// Walks up to the nearest ancestor laid out along the direction's axis
+    /// that has a sibling on that side, then descends into the sibling by
+    /// following `focused_child`. When every matching ancestor is exhausted
+    /// the result depends on `wrapping`: `no` stops, `force` wraps inside
+    /// the innermost matching container, anything else wraps around the
+    /// outermost one.
+    #[must_use]
+    pub fn neighbour(
+        &self,
+        node: LayoutNode,
+        direction: Direction,
+        wrapping: FocusWrapping,
+    ) -> Option<WindowId> {
+        let backwards = direction.is_backwards();
+        let mut node = node;
+        let mut container_id = self.parent_of(node)?;
+        let mut outermost = None;
+
+        loop {
+            let container = self.containers.get(container_id)?;
+            let len = container.children.len();
+            if container.lays_out_along(direction) && len > 1 {
+                let index = container.position(node)?;
+                if let Some(next) = self.step(container, index, direction) {
+                    return self.descend_focused(container.children[next]);
+                }
+                if wrapping == FocusWrapping::Force {
+                    return self.wrap_within(container, backwards);
+                }
+                outermost = Some(container);
+            }
+            match container.parent {
+                Some(parent) => {
+                    node = LayoutNode::Container(container_id);
+                    container_id = parent;
+                }
+                None => break,
+            }
+        }
+
+        match wrapping {
+            FocusWrapping::No => None,
+            _ => self.wrap_within(outermost?, backwards),
+        }
+    }
+
+    /// Index of the child of `container` next to child `index` in
+    /// `direction`: the adjacent one along a split, tabs or a stack, and
+    /// in an automatic layout the nearest one lying that way on screen.
+    fn step(&self, container: &Container, index: usize, direction: Direction) -> Option<usize> {
+        if !container.layout.is_automatic() {
+            return direction.step(index, container.children.len());
+        }
+        let from = self.node_geometry(*container.children.get(index)?)?;
+        let (x, y) = from.center();
+        let (right, bottom) = (from.x + from.width as i32, from.y + from.height as i32);
+        container
+            .children
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != index)
+            .filter_map(|(i, &child)| Some((i, self.node_geometry(child)?)))
+            .filter(|(_, g)| match direction {
+                Direction::Left => g.x + g.width as i32 <= from.x,
+                Direction::Right => g.x >= right,
+                Direction::Up => g.y + g.height as i32 <= from.y,
+                Direction::Down => g.y >= bottom,
+            })
+            .min_by_key(|(_, g)| {
+                let (cx, cy) = g.center();
+                (cx - x).abs() + (cy - y).abs()
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Where the last layout pass put `node`.
+    fn node_geometry(&self, node: LayoutNode) -> Option<Geometry> {
+        match node {
+            LayoutNode::Window(id) => self.window_geometries.get(&id).copied(),
+            LayoutNode::Container(id) => Some(self.containers.get(id)?.geometry),
+        }
+    }
+
+    /// Enter `container` from the opposite edge when wrapping focus.
+    fn wrap_within(&self, container: &Container, backwards: bool) -> Option<WindowId> {
+        let edge = if backwards {
+            container.children.last()
+        } else {
+            container.children.first()
+        };
+        self.descend_focused(*edge?)
+    }
+
+    /// Resolve a node to a window by following `focused_child`.
+    #[must_use]
+    pub fn descend_focused(&self, node: LayoutNode) -> Option<WindowId> {
+        match node {
+            LayoutNode::Window(id) => Some(id),
+            LayoutNode::Container(id) => self.focused_window_in(id),
+        }
+    }
+
+    /// The container directly holding `node`.
+    #[must_use]
+    pub fn parent_of(&self, node: LayoutNode) -> Option<ContainerId> {
+        match node {
+            LayoutNode::Window(id) => self.container_of(id),
+            LayoutNode::Container(id) => self.containers.get(id)?.parent,
+        }
+    }
+
+    /// The nearest master–stack container above `window_id`, together with
+    /// its child that leads to the window.
+    #[must_use]
+    pub fn master_stack_of(&self, window_id: WindowId) -> Option<(ContainerId, LayoutNode)> {
+        let mut node = LayoutNode::Window(window_id);
+        let mut container_id = self.container_of(window_id)?;
+        loop {
+            let container = self.containers.get(container_id)?;
+            if container.layout == LayoutMode::MasterStack {
+                return Some((container_id, node));
+            }
+            node = LayoutNode::Container(container_id);
+            container_id = container.parent?;
+        }
+    }
+
+    /// dwm `zoom`: swap the branch holding `window_id` with the first
+    /// master, or with the next child if it already is the master.
+    pub fn swap_master(&mut self, window_id: WindowId) -> bool {
+        let Some((container_id, node)) = self.master_stack_of(window_id) else {
+            return false;
+        };
+        let Some(container) = self.containers.get_mut(container_id) else {
+            return false;
+        };
+        let Some(index) = container.position(node) else {
+            return false;
+        };
+        let other = usize::from(index == 0);
+        if other >= container.children.len() {
+            return false;
+        }
+        container.children.swap(index, other);
+        self.focus_window(window_id);
+        true
+    }
+
+    /// Every window below `node`, in tree order.
+    #[must_use]
+    pub fn windows_in(&self, node: LayoutNode) -> Vec<WindowId> {
+        let mut windows = Vec::new();
+        let mut pending = vec![node];
+        while let Some(node) = pending.pop() {
+            match node {
+                LayoutNode::Window(id) => windows.push(id),
+                LayoutNode::Container(id) => {
+                    if let Some(container) = self.containers.get(id) {
+                        pending.extend(container.children.iter().rev());
+                    }
+                }
+            }
+        }
+        windows
+    }
+
+    /// Use `gap` between the children of every container.
+    pub fn set_inner_gap(&mut self, gap: u32) {
+        for slot in &mut self.containers.slots {
+            if let Some(container) = slot.container.as_mut().filter(|c| c.gap != gap) {
+                container.gap = gap;
+                slot.changed = true;
+            }
+        }
+    }
+
+    /// Number of windows a layout pass shows: every child of a split, but
+    /// only the focused child of tabbed and stacked containers.
+    pub fn visible_window_count(&self) -> usize {
+        let mut count = 0;
+        let mut pending: Vec<LayoutNode> =
+            self.root.map(LayoutNode::Container).into_iter().collect();
+        while let Some(node) = pending.pop() {
+            match node {
+                LayoutNode::Window(_) => count += 1,
+                LayoutNode::Container(id) => {
+                    let Some(container) = self.containers.get(id) else {
+                        continue;
+                    };
+                    if matches!(container.layout, LayoutMode::Tabbed | LayoutMode::Stacked) {
+                        pending.extend(container.focused());
+                    } else {
+                        pending.extend(&container.children);
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// `container_id` and every container below it.
+    fn containers_in(&self, container_id: ContainerId) -> Vec<ContainerId> {
+        let mut ids = vec![container_id];
+        let mut index = 0;
+        while let Some(&id) = ids.get(index) {
+            if let Some(container) = self.containers.get(id) {
+                ids.extend(container.children.iter().filter_map(|child| {
+                    if let LayoutNode::Container(child) = child {
+                        Some(*child)
+                    } else {
+                        None
+                    }
+                }));
+            }
+            index += 1;
+        }
+        ids
+    }
+
+    /// Remove `container_id` and everything below it from the tree.
+    ///
+    /// Ancestors left empty are pruned, as when removing a window.
+    pub fn detach_subtree(&mut self, container_id: ContainerId) -> Option<Subtree> {
+        let parent = self.containers.get(container_id)?.parent;
+        let windows = self.windows_in(LayoutNode::Container(container_id));
+        let containers: Vec<Container> = self
+            .containers_in(container_id)
+            .into_iter()
+            .filter_map(|id| self.containers.remove(id))
+            .collect();
+
+        let survivor = if let Some(parent_id) = parent {
+            let now_empty = self.containers.get_mut(parent_id).is_some_and(|parent| {
+                parent.remove_node(LayoutNode::Container(container_id));
+                parent.is_empty()
+            });
+            if now_empty {
+                self.remove_empty_container(parent_id)
+            } else {
+                Some(parent_id)
+            }
+        } else {
+            self.root = None;
+            None
+        };
+
+        if self
+            .focused_container
+            .map_or(true, |id| !self.containers.contains_key(id))
+        {
+            self.focused_container = survivor.map(|id| self.focused_leaf_container(id));
+        }
+        for window_id in &windows {
+            self.parents.remove(window_id);
+            self.size_hints.remove(window_id);
+            self.window_geometries.remove(window_id);
+        }
+
+        Some(Subtree {
+            root: container_id,
+            containers,
+            windows,
+        })
+    }
+
+    /// Insert a detached subtree next to the focused window, or make it the
+    /// root of an empty tree. Focus moves into the subtree.
+    ///
+    /// The subtree's containers get new IDs in this tree; the returned map
+    /// takes each old ID to its new one.
+    pub fn attach_subtree(&mut self, subtree: Subtree) -> HashMap<ContainerId, ContainerId> {
+        let ids: HashMap<ContainerId, ContainerId> = subtree
+            .containers
+            .into_iter()
+            .map(|container| (container.id, self.containers.insert(container)))
+            .collect();
+        for &id in ids.values() {
+            let Some(container) = self.containers.get_mut(id) else {
+                continue;
+            };
+            container.parent = container.parent.and_then(|parent| ids.get(&parent).copied());
+            for child in &mut container.children {
+                match child {
+                    LayoutNode::Container(child) => *child = ids[child],
+                    LayoutNode::Window(window_id) => {
+                        self.parents.insert(*window_id, id);
+                    }
+                }
+            }
+        }
+        let root = ids[&subtree.root];
+
+        if let Some(target) = self.focused_container.or(self.root) {
+            let index = self
+                .containers
+                .get(target)
+                .map_or(0, |container| container.focused_child + 1);
+            self.insert_node(target, index, LayoutNode::Container(root));
+            self.focus_path(target, LayoutNode::Container(root));
+        } else {
+            self.root = Some(root);
+        }
+        self.focused_container = Some(self.focused_leaf_container(root));
+        ids
+    }
+
+    /// The first placeholder, breadth-first from the root, waiting for a
+    /// window like `window`.
+    #[must_use]
+    pub fn placeholder_for(&self, window: &Window) -> Option<ContainerId> {
+        self.containers_in(self.root?).into_iter().find(|id| {
+            self.containers.get(*id).is_some_and(|container| {
+                container.is_placeholder()
+                    && container
+                        .swallows
+                        .as_ref()
+                        .is_some_and(|criteria| criteria.matches(window))
+            })
+        })
+    }
+
+    /// Put `window_id` in the place of the placeholder `container_id`,
+    /// keeping its share of the parent.
+    pub fn swallow(&mut self, container_id: ContainerId, window_id: WindowId) -> bool {
+        if !self
+            .containers
+            .get(container_id)
+            .is_some_and(Container::is_placeholder)
+        {
+            return false;
+        }
+        let Some(parent_id) = self.containers.get(container_id).and_then(|c| c.parent) else {
+            return false;
+        };
+        let Some(slot) = self.containers.get_mut(parent_id).and_then(|parent| {
+            let index = parent.position(LayoutNode::Container(container_id))?;
+            parent.children.get_mut(index)
+        }) else {
+            return false;
+        };
+        *slot = LayoutNode::Window(window_id);
+        self.containers.remove(container_id);
+        self.parents.insert(window_id, parent_id);
+        self.focus_window(window_id);
+        true
+    }
+
+    /// Find the split a resize of `window_id` along `axis` acts on.
+    ///
+    /// Walks up to the nearest split container along `axis` in which the
+    /// window's branch has a sibling on `edge` (on either side, preferring
+    /// the next one, when no edge is given). Returns that container, the
+    /// branch's index and the sibling's index. A master–stack container
+    /// split along `axis` counts too, its master and stack areas being
+    /// the two sides; the sibling is then the nearest child across.
+    #[must_use]
+    pub fn resize_target(
+        &self,
+        window_id: WindowId,
+        axis: SplitDirection,
+        edge: Option<Direction>,
+    ) -> Option<(ContainerId, usize, usize)> {
+        let mut node = LayoutNode::Window(window_id);
+        let mut container_id = self.container_of(window_id)?;
+        loop {
+            let container = self.containers.get(container_id)?;
+            let len = container.children.len();
+            if container.layout == LayoutMode::MasterStack
+                && container.split_direction == axis
+                && (1..len).contains(&container.master_count)
+            {
+                let index = container.position(node)?;
+                let masters = container.master_count;
+                let in_master = index < masters;
+                // A master's far edge faces the stack, a stack window's near one.
+                if edge.map_or(true, |direction| direction.is_backwards() != in_master) {
+                    let neighbour = if in_master { masters } else { masters - 1 };
+                    return Some((container_id, index, neighbour));
+                }
+            } else if container.layout == LayoutMode::Split && container.split_direction == axis {
+                let index = container.position(node)?;
+                let neighbour = edge.map_or_else(
+                    || {
+                        Direction::Right
+                            .step(index, len)
+                            .or_else(|| index.checked_sub(1))
+                    },
+                    |direction| direction.step(index, len),
+                );
+                if let Some(neighbour) = neighbour {
+                    return Some((container_id, index, neighbour));
+                }
             }
+            node = LayoutNode::Container(container_id);
+            container_id = container.parent?;
         }
     }
+
+    /// Move `window_id` one step in `direction` (i3 `move left|right|up|down`).
+    ///
+    /// Focus stays on the moved window.
+    pub fn move_window(&mut self, window_id: WindowId, direction: Direction) -> bool {
+        let moved = self.move_node(LayoutNode::Window(window_id), direction);
+        if moved {
+            self.focus_window(window_id);
+        }
+        moved
+    }
+
+    /// Move `node` one step in `direction`, restructuring the tree like i3.
+    ///
+    /// Inside a container laid out along the direction's axis the node swaps
+    /// with a neighbouring window or enters a neighbouring container at the
+    /// edge facing it. At the container's edge it leaves for the nearest
+    /// ancestor along that axis, entering the adjacent branch there if one
+    /// exists. If no ancestor runs along the axis, the root is re-oriented
+    /// first. Containers left empty are removed.
+    pub fn move_node(&mut self, node: LayoutNode, direction: Direction) -> bool {
+        let Some(mut old_parent) = self.parent_of(node) else {
+            return false;
+        };
+
+        let mut along = self.ancestor_along(Some(old_parent), direction);
+        let ancestor_id = loop {
+            let Some(ancestor_id) = along else {
+                if !self.reorient_root(direction) {
+                    return false;
+                }
+                // The node now sits in the wrapper, which is pruned if
+                // the node leaves it empty.
+                let Some(wrapper) = self.parent_of(node) else {
+                    return false;
+                };
+                old_parent = wrapper;
+                along = self.root;
+                continue;
+            };
+            let Some(parent) = self.parent_of(node) else {
+                return false;
+            };
+            if ancestor_id != parent {
+                break ancestor_id;
+            }
+
+            let Some(container) = self.containers.get(ancestor_id) else {
+                return false;
+            };
+            let Some(index) = container.position(node) else {
+                return false;
+            };
+            let next = self.step(container, index, direction);
+            let Some(container) = self.containers.get_mut(ancestor_id) else {
+                return false;
+            };
+            if let Some(next) = next {
+                match container.children[next] {
+                    LayoutNode::Window(_) => {
+                        container.children.swap(index, next);
+                        if container.ratios.len() == container.children.len() {
+                            container.ratios.swap(index, next);
+                            container.pinned.swap(index, next);
+                        }
+                        container.focused_child = next;
+                    }
+                    LayoutNode::Container(branch) => self.move_into_branch(node, branch, direction),
+                }
+                self.prune_if_empty(old_parent);
+                return true;
+            }
+            if self.root == Some(ancestor_id) {
+                return false;
+            }
+            let grandparent = container.parent;
+            along = self.ancestor_along(grandparent, direction);
+        };
+
+        // Leave the current container: land beside the ancestor's child that
+        // holds us, or inside the branch next to it.
+        let Some(above) = self.child_towards(ancestor_id, node) else {
+            return false;
+        };
+        let Some(container) = self.containers.get(ancestor_id) else {
+            return false;
+        };
+        let Some(index) = container.position(above) else {
+            return false;
+        };
+        let next = self
+            .step(container, index, direction)
+            .map(|i| container.children[i]);
+        if let Some(LayoutNode::Container(branch)) = next {
+            self.move_into_branch(node, branch, direction);
+        } else {
+            self.detach(node);
+            let offset = usize::from(!direction.is_backwards());
+            self.insert_node(ancestor_id, index + offset, node);
+        }
+        self.prune_if_empty(old_parent);
+        true
+    }
+
+    /// Insert `node` into `branch` next to the window nearest the edge we
+    /// enter from (or its focused window when `branch` runs across the axis).
+    fn move_into_branch(&mut self, node: LayoutNode, branch: ContainerId, direction: Direction) {
+        let mut target = branch;
+        let index = loop {
+            let Some(container) = self.containers.get(target) else {
+                return;
+            };
+            let index = if !container.lays_out_along(direction) {
+                container.focused_child
+            } else if direction.is_backwards() {
+                container.children.len().saturating_sub(1)
+            } else {
+                0
+            };
+            match container.children.get(index) {
+                Some(LayoutNode::Container(inner)) => target = *inner,
+                Some(LayoutNode::Window(_)) => break index,
+                None => return,
+            }
+        };
+
+        let after = direction.is_backwards()
+            || self
+                .containers
+                .get(target)
+                .is_some_and(|c| !c.lays_out_along(direction));
+        self.detach(node);
+        self.insert_node(target, index + usize::from(after), node);
+    }
+
+    /// Nearest container at or above `start` laid out along `direction`.
+    fn ancestor_along(
+        &self,
+        start: Option<ContainerId>,
+        direction: Direction,
+    ) -> Option<ContainerId> {
+        let mut current = start;
+        while let Some(id) = current {
+            let container = self.containers.get(id)?;
+            if container.lays_out_along(direction) {
+                return Some(id);
+            }
+            current = container.parent;
+        }
+        None
+    }
+
+    /// The direct child of `ancestor` on the path down to `node`.
+    fn child_towards(&self, ancestor: ContainerId, node: LayoutNode) -> Option<LayoutNode> {
+        let mut current = node;
+        loop {
+            let parent = self.parent_of(current)?;
+            if parent == ancestor {
+                return Some(current);
+            }
+            current = LayoutNode::Container(parent);
+        }
+    }
+
+    /// Wrap the root's children in a new container and re-orient the root
+    /// along `direction` (i3 forcing the workspace orientation).
+    fn reorient_root(&mut self, direction: Direction) -> bool {
+        let Some(root_id) = self.root else {
+            return false;
+        };
+        let Some(root) = self.containers.get_mut(root_id) else {
+            return false;
+        };
+
+        let mut wrapper = Container::new(root.layout, root.split_direction);
+        wrapper.parent = Some(root_id);
+        wrapper.gap = root.gap;
+        wrapper.children = std::mem::take(&mut root.children);
+        wrapper.ratios = std::mem::take(&mut root.ratios);
+        wrapper.pinned = std::mem::take(&mut root.pinned);
+        wrapper.focused_child = root.focused_child;
+        root.set_split(direction.axis());
+        let len = wrapper.children.len();
+
+        let wrapper_id = self.containers.insert(wrapper);
+        for i in 0..len {
+            let child = self.containers[wrapper_id].children[i];
+            self.adopt(wrapper_id, child);
+        }
+        if let Some(root) = self.containers.get_mut(root_id) {
+            root.add_child(LayoutNode::Container(wrapper_id));
+            root.focused_child = 0;
+        }
+        if self.focused_container == Some(root_id) {
+            self.focused_container = Some(wrapper_id);
+        }
+        true
+    }
+
+    /// Remove `node` from its parent without pruning the parent.
+    fn detach(&mut self, node: LayoutNode) {
+        if let Some(parent) = self.parent_of(node) {
+            if let Some(container) = self.containers.get_mut(parent) {
+                container.remove_node(node);
+            }
+        }
+    }
+
+    /// Insert `node` into `container_id` at `index` and focus it there.
+    fn insert_node(&mut self, container_id: ContainerId, index: usize, node: LayoutNode) {
+        let Some(container) = self.containers.get_mut(container_id) else {
+            return;
+        };
+        let index = index.min(container.children.len());
+        container.insert_child(index, node);
+        container.focused_child = index;
+        self.adopt(container_id, node);
+    }
+
+    /// Remove `container_id` (and any emptied ancestors) if it has no
+    /// children left.
+    fn prune_if_empty(&mut self, container_id: ContainerId) {
+        if self
+            .containers
+            .get(container_id)
+            .is_some_and(Container::is_empty)
+        {
+            self.remove_empty_container(container_id);
+        }
+    }
+
+    pub fn toggle_split(&mut self) {
+        if let Some(container_id) = self.focused_container {
+            if let Some(container) = self.containers.get_mut(container_id) {
+                container.split_direction = container.split_direction.toggle();
+            }
+        }
+    }
+}
+
+/// How far a node can shrink and grow along one axis.
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    min: u32,
+    max: u32,
+}
+
+impl Extent {
+    const FLEXIBLE: Self = Self {
+        min: 0,
+        max: u32::MAX,
+    };
+
+    fn clamp(self, length: f64) -> f64 {
+        length.clamp(f64::from(self.min), f64::from(self.max))
+    }
+}
+
+/// Round `length` down to `base` plus a whole number of `increment`s.
+const fn snap(length: u32, base: u32, increment: u32) -> u32 {
+    if length < base {
+        return length;
+    }
+    base + (length - base) / increment * increment
+}
+
+/// Cut `geometry` into `n` equal slices along `direction`, `gap` apart.
+/// The last slice absorbs any rounding remainder.
+pub(crate) fn split_evenly(
+    geometry: Geometry,
+    n: u32,
+    gap: u32,
+    direction: SplitDirection,
+) -> impl Iterator<Item = Geometry> {
+    let (start, length) = match direction {
+        SplitDirection::Horizontal => (geometry.x, geometry.width),
+        SplitDirection::Vertical => (geometry.y, geometry.height),
+    };
+    let size = length.saturating_sub(gap * n.saturating_sub(1)) / n.max(1);
+    let end = start + length as i32;
+    (0..n).map(move |i| {
+        let offset = start + (i * (size + gap)) as i32;
+        let size = if i == n - 1 {
+            (end - offset).max(0) as u32
+        } else {
+            size
+        };
+        match direction {
+            SplitDirection::Horizontal => Geometry::new(offset, geometry.y, size, geometry.height),
+            SplitDirection::Vertical => Geometry::new(geometry.x, offset, geometry.width, size),
+        }
+    })
 }
 
 /// Direction for focus/move operations.
@@ -445,6 +2243,29 @@ impl Direction {
     pub fn is_vertical(self) -> bool {
         matches!(self, Self::Up | Self::Down)
     }
+
+    /// Split direction whose axis this direction moves along.
+    #[must_use]
+    pub const fn axis(self) -> SplitDirection {
+        match self {
+            Self::Left | Self::Right => SplitDirection::Horizontal,
+            Self::Up | Self::Down => SplitDirection::Vertical,
+        }
+    }
+
+    /// Whether this direction points towards lower child indices.
+    const fn is_backwards(self) -> bool {
+        matches!(self, Self::Left | Self::Up)
+    }
+
+    /// Index of the sibling one step from `index` among `len` children.
+    fn step(self, index: usize, len: usize) -> Option<usize> {
+        if self.is_backwards() {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&i| i < len)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -461,6 +2282,62 @@ mod tests {
         assert!((container.ratios[0] - 0.5).abs() < 0.001);
     }
 
+    #[test]
+    fn test_ratios_survive_added_and_removed_children() {
+        let mut container = Container::new_split(SplitDirection::Horizontal);
+        for id in 1..=3 {
+            container.add_child(LayoutNode::Window(WindowId(id)));
+        }
+        let assert_ratios = |container: &Container, expected: &[f64]| {
+            assert_eq!(container.ratios.len(), expected.len());
+            for (ratio, expected) in container.ratios.iter().zip(expected) {
+                assert!((ratio - expected).abs() < 0.001, "{:?}", container.ratios);
+            }
+        };
+
+        // A new child takes its share proportionally from a resized split.
+        container.ratios = vec![0.5, 0.25, 0.25];
+        container.add_child(LayoutNode::Window(WindowId(4)));
+        assert_ratios(&container, &[0.375, 0.1875, 0.1875, 0.25]);
+        container.remove_child(3);
+        assert_ratios(&container, &[0.5, 0.25, 0.25]);
+
+        // A pinned share stays put; only its siblings give and take.
+        container.pin_ratio(0, 0.6);
+        assert_ratios(&container, &[0.6, 0.2, 0.2]);
+        container.insert_child(1, LayoutNode::Window(WindowId(4)));
+        let third = 0.4 / 3.0;
+        assert_ratios(&container, &[0.6, third, third, third]);
+        container.remove_child(1);
+        assert_ratios(&container, &[0.6, 0.2, 0.2]);
+    }
+
+    #[test]
+    fn test_container_layout_cycling() {
+        let mut container = Container::new_split(SplitDirection::Vertical);
+        container.layout = LayoutMode::Tabbed;
+        container.toggle_split_layout();
+        assert_eq!(container.layout, LayoutMode::Split);
+        assert_eq!(container.split_direction, SplitDirection::Vertical);
+        container.toggle_split_layout();
+        assert_eq!(container.split_direction, SplitDirection::Horizontal);
+
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            container.cycle_all_layouts();
+            seen.push((container.layout, container.split_direction));
+        }
+        assert_eq!(
+            seen,
+            vec![
+                (LayoutMode::Split, SplitDirection::Vertical),
+                (LayoutMode::Stacked, SplitDirection::Vertical),
+                (LayoutMode::Tabbed, SplitDirection::Vertical),
+                (LayoutMode::Split, SplitDirection::Horizontal),
+            ]
+        );
+    }
+
     #[test]
     fn test_layout_tree_add_window() {
         let mut tree = LayoutTree::new();
@@ -468,10 +2345,424 @@ mod tests {
         tree.add_window(WindowId(1), &config);
         tree.add_window(WindowId(2), &config);
         assert!(tree.root.is_some());
-        let root = tree.containers.get(&tree.root.unwrap()).unwrap();
+        let root = tree.containers.get(tree.root.unwrap()).unwrap();
         assert_eq!(root.children.len(), 2);
     }
 
+    #[test]
+    fn test_container_ids_stay_unique() {
+        let config = Config::default();
+        let mut tree = LayoutTree::with_id(70_000);
+        tree.add_window(WindowId(1), &config);
+        let old_root = tree.root.unwrap();
+
+        // Tree IDs beyond 16 bits are kept apart.
+        let mut other = LayoutTree::with_id(70_000 - (1 << 16));
+        other.add_window(WindowId(2), &config);
+        assert!(!other.containers.contains_key(old_root));
+        assert!(!tree.containers.contains_key(other.root.unwrap()));
+
+        // A successor reuses the slots under new generations.
+        let mut successor = tree.successor();
+        successor.add_window(WindowId(1), &config);
+        assert_ne!(successor.root, Some(old_root));
+        assert!(!successor.containers.contains_key(old_root));
+    }
+
+    #[test]
+    fn test_split_nests_focused_window() {
+        let mut tree = LayoutTree::new();
+        let config = Config::default();
+        tree.add_window(WindowId(1), &config);
+        tree.add_window(WindowId(2), &config);
+        assert!(tree.split(WindowId(2), SplitDirection::Vertical));
+        tree.add_window(WindowId(3), &config);
+
+        let root_id = tree.root.unwrap();
+        let split_id = tree.container_of(WindowId(3)).unwrap();
+        assert_ne!(split_id, root_id);
+        let split = &tree.containers[split_id];
+        assert_eq!(split.parent, Some(root_id));
+        assert_eq!(split.split_direction, SplitDirection::Vertical);
+        assert_eq!(
+            split.children,
+            vec![
+                LayoutNode::Window(WindowId(2)),
+                LayoutNode::Window(WindowId(3))
+            ]
+        );
+        assert_eq!(tree.focused_window(), Some(WindowId(3)));
+
+        tree.remove_window(WindowId(2));
+        tree.remove_window(WindowId(3));
+        assert!(!tree.containers.contains_key(split_id));
+        assert_eq!(
+            tree.containers[root_id].children,
+            vec![LayoutNode::Window(WindowId(1))]
+        );
+        assert_eq!(tree.focused_container, Some(root_id));
+    }
+
+    #[test]
+    fn test_new_window_placement() {
+        let config = Config::default();
+        let mut tree = LayoutTree::new();
+        for id in 1..=3 {
+            tree.add_window(WindowId(id), &config);
+        }
+        let root_id = tree.root.unwrap();
+        let order = |tree: &LayoutTree| tree.windows_in(LayoutNode::Container(root_id));
+
+        tree.focus_window(WindowId(2));
+        tree.place_window(WindowId(4), NewWindowPlacement::AfterFocused, &config);
+        tree.place_window(WindowId(5), NewWindowPlacement::End, &config);
+        tree.place_window(WindowId(6), NewWindowPlacement::Start, &config);
+        let expected: Vec<WindowId> = [6, 1, 2, 4, 3, 5].map(WindowId).into();
+        assert_eq!(order(&tree), expected);
+        assert_eq!(tree.focused_window(), Some(WindowId(6)));
+
+        // Splitting the narrow focused window stacks the new one below it.
+        tree.calculate_layout(Geometry::new(0, 0, 1200, 800), 0);
+        tree.place_window(WindowId(7), NewWindowPlacement::SplitFocused, &config);
+        let split_id = tree.container_of(WindowId(7)).unwrap();
+        assert_eq!(
+            children(&tree, split_id),
+            [6, 7].map(|id| LayoutNode::Window(WindowId(id)))
+        );
+        assert_eq!(
+            tree.containers[split_id].split_direction,
+            SplitDirection::Vertical
+        );
+
+        // As master: first in the focused container.
+        tree.place_window(WindowId(8), NewWindowPlacement::AsMaster, &config);
+        assert_eq!(
+            children(&tree, split_id)[0],
+            LayoutNode::Window(WindowId(8))
+        );
+    }
+
+    #[test]
+    fn test_relayout_only_dirty_subtrees() {
+        let config = Config::default();
+        let mut tree = LayoutTree::new();
+        for id in 1..=3 {
+            tree.add_window(WindowId(id), &config);
+        }
+        let area = Geometry::new(0, 0, 1200, 800);
+        tree.calculate_layout(area, 0);
+        tree.focus_window(WindowId(2));
+        tree.place_window(WindowId(4), NewWindowPlacement::SplitFocused, &config);
+        tree.focus_window(WindowId(3));
+        tree.place_window(WindowId(5), NewWindowPlacement::SplitFocused, &config);
+        let left = tree.container_of(WindowId(2)).unwrap();
+        let right = tree.container_of(WindowId(3)).unwrap();
+        tree.calculate_layout(area, 0);
+        let before = tree.window_geometries.clone();
+
+        let root = tree.root.unwrap();
+
+        // Nothing changed: nothing is laid out again.
+        tree.calculate_layout(area, 0);
+        assert!(!tree.propagate_changes(root));
+        assert_eq!(tree.window_geometries, before);
+
+        // Resizing inside one branch leaves its sibling branch alone.
+        tree.containers.get_mut(left).unwrap().pin_ratio(0, 0.7);
+        assert!(tree.propagate_changes(root));
+        assert!(tree.containers.is_changed(left));
+        assert!(!tree.containers.is_changed(right));
+        tree.calculate_layout(area, 0);
+        assert_ne!(tree.window_geometries[&WindowId(2)], before[&WindowId(2)]);
+        for id in [1, 3, 5].map(WindowId) {
+            assert_eq!(tree.window_geometries[&id], before[&id]);
+        }
+    }
+
+    #[test]
+    fn test_autotiling_splits_along_longer_side() {
+        let mut config = Config::default();
+        config.general.autotiling = true;
+        let mut tree = LayoutTree::new();
+        tree.add_window(WindowId(1), &config);
+        tree.add_window(WindowId(2), &config);
+        tree.calculate_layout(Geometry::new(0, 0, 1000, 800), 0);
+
+        // Both halves are taller than wide, so the next window stacks below.
+        tree.add_window(WindowId(3), &config);
+        let split = &tree.containers[tree.container_of(WindowId(3)).unwrap()];
+        assert_ne!(Some(split.id), tree.root);
+        assert_eq!(split.split_direction, SplitDirection::Vertical);
+        assert_eq!(
+            split.children,
+            vec![
+                LayoutNode::Window(WindowId(2)),
+                LayoutNode::Window(WindowId(3))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_master_stack_layout() {
+        let mut config = Config::default();
+        config.general.default_layout = LayoutMode::MasterStack;
+        config.gaps.inner = 0;
+        let mut tree = LayoutTree::new();
+        for id in 1..=3 {
+            tree.add_window(WindowId(id), &config);
+        }
+        let root_id = tree.root.unwrap();
+        tree.containers.get_mut(root_id).unwrap().master_ratio = 0.6;
+        tree.calculate_layout(Geometry::new(0, 0, 1000, 800), 0);
+
+        let geo = |tree: &LayoutTree, id| tree.window_geometries[&WindowId(id)];
+        assert_eq!(geo(&tree, 1), Geometry::new(0, 0, 600, 800));
+        assert_eq!(geo(&tree, 2), Geometry::new(600, 0, 400, 400));
+        assert_eq!(geo(&tree, 3), Geometry::new(600, 400, 400, 400));
+
+        // Two masters share the master column; the last window fills the stack.
+        tree.containers.get_mut(root_id).unwrap().master_count = 2;
+        tree.calculate_layout(Geometry::new(0, 0, 1000, 800), 0);
+        assert_eq!(geo(&tree, 2), Geometry::new(0, 400, 600, 400));
+        assert_eq!(geo(&tree, 3), Geometry::new(600, 0, 400, 800));
+
+        assert!(tree.swap_master(WindowId(3)));
+        assert_eq!(
+            tree.containers[root_id].children[0],
+            LayoutNode::Window(WindowId(3))
+        );
+        assert_eq!(tree.focused_window(), Some(WindowId(3)));
+    }
+
+    #[test]
+    fn test_automatic_layout_neighbours_follow_geometry() {
+        let mut config = Config::default();
+        config.general.default_layout = LayoutMode::MasterStack;
+        config.gaps.inner = 0;
+        let mut tree = LayoutTree::new();
+        for id in 1..=4 {
+            tree.add_window(WindowId(id), &config);
+        }
+        let root_id = tree.root.unwrap();
+        let area = Geometry::new(0, 0, 1000, 900);
+        tree.calculate_layout(area, 0);
+        let neighbour = |tree: &LayoutTree, id, direction| {
+            tree.neighbour(LayoutNode::Window(WindowId(id)), direction, FocusWrapping::No)
+        };
+
+        // Master on the left, 2–4 stacked on the right.
+        assert_eq!(neighbour(&tree, 3, Direction::Left), Some(WindowId(1)));
+        assert_eq!(neighbour(&tree, 3, Direction::Up), Some(WindowId(2)));
+        assert_eq!(neighbour(&tree, 3, Direction::Down), Some(WindowId(4)));
+        assert_eq!(neighbour(&tree, 4, Direction::Right), None);
+        assert_eq!(neighbour(&tree, 1, Direction::Up), None);
+        assert_eq!(neighbour(&tree, 1, Direction::Right), Some(WindowId(3)));
+
+        // Grid: 1 2 / 3 4.
+        tree.containers.get_mut(root_id).unwrap().layout = LayoutMode::Grid;
+        tree.calculate_layout(area, 0);
+        assert_eq!(neighbour(&tree, 1, Direction::Down), Some(WindowId(3)));
+        assert_eq!(neighbour(&tree, 4, Direction::Left), Some(WindowId(3)));
+        assert_eq!(neighbour(&tree, 2, Direction::Right), None);
+
+        // Moving swaps with the window on screen rather than the next child.
+        assert!(tree.move_window(WindowId(1), Direction::Down));
+        assert_eq!(
+            tree.containers[root_id].children,
+            [3, 2, 1, 4].map(|id| LayoutNode::Window(WindowId(id)))
+        );
+    }
+
+    #[test]
+    fn test_dwindle_and_grid_layouts() {
+        let mut config = Config::default();
+        config.gaps.inner = 10;
+        let mut tree = LayoutTree::new();
+        for id in 1..=5 {
+            tree.add_window(WindowId(id), &config);
+        }
+        let root_id = tree.root.unwrap();
+        let area = Geometry::new(0, 0, 1010, 810);
+        let geo = |tree: &LayoutTree, id| tree.window_geometries[&WindowId(id)];
+
+        tree.containers.get_mut(root_id).unwrap().layout = LayoutMode::Dwindle;
+        tree.calculate_layout(area, 0);
+        assert_eq!(geo(&tree, 1), Geometry::new(0, 0, 500, 810));
+        assert_eq!(geo(&tree, 2), Geometry::new(510, 0, 500, 400));
+        assert_eq!(geo(&tree, 3), Geometry::new(510, 410, 245, 400));
+        assert_eq!(geo(&tree, 4), Geometry::new(765, 410, 245, 195));
+        assert_eq!(geo(&tree, 5), Geometry::new(765, 615, 245, 195));
+
+        // Five windows: three columns, the second row stretched over two.
+        tree.containers.get_mut(root_id).unwrap().layout = LayoutMode::Grid;
+        tree.calculate_layout(area, 0);
+        assert_eq!(geo(&tree, 1), Geometry::new(0, 0, 330, 400));
+        assert_eq!(geo(&tree, 3), Geometry::new(680, 0, 330, 400));
+        assert_eq!(geo(&tree, 4), Geometry::new(0, 410, 500, 400));
+        assert_eq!(geo(&tree, 5), Geometry::new(510, 410, 500, 400));
+    }
+
+    #[test]
+    fn test_split_honours_size_hints() {
+        let mut config = Config::default();
+        config.gaps.inner = 0;
+        let mut tree = LayoutTree::new();
+        for id in 1..=3 {
+            tree.add_window(WindowId(id), &config);
+        }
+        let hints = |min_width, max_width, width_increment| SizeHints {
+            min_width,
+            max_width,
+            width_increment,
+            ..SizeHints::default()
+        };
+        tree.set_size_hints(WindowId(1), hints(Some(500), None, None));
+        tree.set_size_hints(WindowId(2), hints(None, Some(100), None));
+        tree.set_size_hints(WindowId(3), hints(None, None, Some(7)));
+        let area = Geometry::new(0, 0, 1000, 800);
+        let geo = |tree: &LayoutTree, id| tree.window_geometries[&WindowId(id)];
+
+        // The capped window's share goes to the others; the terminal snaps
+        // to its increment and the first window takes the leftover pixel.
+        tree.calculate_layout(area, 0);
+        assert_eq!(geo(&tree, 1), Geometry::new(0, 0, 501, 800));
+        assert_eq!(geo(&tree, 2), Geometry::new(501, 0, 100, 800));
+        assert_eq!(geo(&tree, 3), Geometry::new(601, 0, 399, 800));
+        assert_eq!(tree.unfit_windows, []);
+
+        // Minimums beyond the available width are shrunk alike, side by
+        // side, and reported.
+        tree.set_size_hints(WindowId(1), hints(Some(800), None, None));
+        tree.set_size_hints(WindowId(2), hints(Some(300), None, None));
+        tree.calculate_layout(area, 0);
+        assert_eq!(geo(&tree, 1).width, 727);
+        assert_eq!(geo(&tree, 2), Geometry::new(727, 0, 272, 800));
+        assert_eq!(tree.unfit_windows, vec![WindowId(1), WindowId(2)]);
+    }
+
+    #[test]
+    fn test_focus_direction_wrapping() {
+        let mut tree = LayoutTree::new();
+        let config = Config::default();
+        for i in 1..=3 {
+            tree.add_window(WindowId(i), &config);
+        }
+        tree.split(WindowId(3), SplitDirection::Vertical);
+        tree.add_window(WindowId(4), &config);
+        // Layout: 1 | 2 | (3 / 4), focus on 4.
+
+        let left = tree.focus_direction(Direction::Left, FocusWrapping::Yes);
+        assert_eq!(left, Some(WindowId(2)));
+        // Re-entering the split follows its focused child, not its first one.
+        let right = tree.focus_direction(Direction::Right, FocusWrapping::Yes);
+        assert_eq!(right, Some(WindowId(4)));
+
+        // At the right edge: `yes` wraps around the row, `force` wraps
+        // inside the innermost split, `no` stays put.
+        let node = LayoutNode::Window(WindowId(4));
+        assert_eq!(
+            tree.neighbour(node, Direction::Right, FocusWrapping::Yes),
+            Some(WindowId(1))
+        );
+        assert_eq!(
+            tree.neighbour(node, Direction::Down, FocusWrapping::Force),
+            Some(WindowId(3))
+        );
+        assert_eq!(
+            tree.neighbour(node, Direction::Right, FocusWrapping::No),
+            None
+        );
+        assert_eq!(
+            tree.neighbour(node, Direction::Up, FocusWrapping::No),
+            Some(WindowId(3))
+        );
+    }
+
+    fn children(tree: &LayoutTree, id: ContainerId) -> Vec<LayoutNode> {
+        tree.containers[id].children.clone()
+    }
+
+    #[test]
+    fn test_move_window_swaps_and_enters_branches() {
+        let mut tree = LayoutTree::new();
+        let config = Config::default();
+        for i in 1..=3 {
+            tree.add_window(WindowId(i), &config);
+        }
+        let root = tree.root.unwrap();
+        let w = |i| LayoutNode::Window(WindowId(i));
+
+        // Plain swap with a sibling window.
+        assert!(tree.move_window(WindowId(3), Direction::Left));
+        assert_eq!(children(&tree, root), vec![w(1), w(3), w(2)]);
+
+        // 1 | 3 | (2 / 4): moving 3 right enters the vertical split after
+        // its focused window.
+        tree.split(WindowId(2), SplitDirection::Vertical);
+        tree.add_window(WindowId(4), &config);
+        let split = tree.container_of(WindowId(4)).unwrap();
+        assert!(tree.move_window(WindowId(3), Direction::Right));
+        assert_eq!(
+            children(&tree, root),
+            vec![w(1), LayoutNode::Container(split)]
+        );
+        assert_eq!(children(&tree, split), vec![w(2), w(4), w(3)]);
+        assert_eq!(tree.focused_window(), Some(WindowId(3)));
+
+        // Moving out of the split's left edge lands beside it in the root.
+        tree.move_window(WindowId(2), Direction::Left);
+        assert_eq!(
+            children(&tree, root),
+            vec![w(1), w(2), LayoutNode::Container(split)]
+        );
+
+        // Emptying the split removes it.
+        tree.move_window(WindowId(4), Direction::Left);
+        tree.move_window(WindowId(3), Direction::Left);
+        assert!(!tree.containers.contains_key(split));
+        assert_eq!(children(&tree, root), vec![w(1), w(2), w(4), w(3)]);
+    }
+
+    #[test]
+    fn test_move_window_across_axis_reorients_root() {
+        let mut tree = LayoutTree::new();
+        let config = Config::default();
+        tree.add_window(WindowId(1), &config);
+        tree.add_window(WindowId(2), &config);
+        let root = tree.root.unwrap();
+
+        assert!(tree.move_window(WindowId(2), Direction::Up));
+        let root_container = &tree.containers[root];
+        assert_eq!(root_container.split_direction, SplitDirection::Vertical);
+        assert_eq!(root_container.children[0], LayoutNode::Window(WindowId(2)));
+        let LayoutNode::Container(rest) = root_container.children[1] else {
+            panic!("expected the old row to be wrapped");
+        };
+        assert_eq!(tree.containers[rest].parent, Some(root));
+        assert_eq!(children(&tree, rest), vec![LayoutNode::Window(WindowId(1))]);
+    }
+
+    #[test]
+    fn test_move_single_window_keeps_whole_area() {
+        let area = Geometry::new(0, 0, 1920, 1080);
+        for direction in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            let mut tree = LayoutTree::new();
+            tree.add_window(WindowId(1), &Config::default());
+            tree.move_window(WindowId(1), direction);
+
+            let root = tree.root.unwrap();
+            assert_eq!(tree.containers.values().count(), 1, "moving {direction:?}");
+            assert_eq!(children(&tree, root), vec![LayoutNode::Window(WindowId(1))]);
+            tree.calculate_layout(area, 0);
+            assert_eq!(tree.window_geometries[&WindowId(1)], area, "moving {direction:?}");
+        }
+    }
+
     #[test]
     fn test_geometry_split() {
         let geo = Geometry::new(0, 0, 1000, 500);
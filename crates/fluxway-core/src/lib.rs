@@ -17,8 +17,9 @@
 //! let mut core = Core::new(config);
 //!
 //! // Backend tells core a window appeared
+//! let id = core.next_window_id();
 //! let actions = core.handle_event(CoreEvent::WindowMapped {
-//!     id: core.next_window_id(),
+//!     id,
 //!     app_id: Some("firefox".into()),
 //!     title: Some("Mozilla Firefox".into()),
 //!     pid: None,
@@ -31,10 +32,16 @@
 //! ```
 
 pub mod config;
+pub mod decoration;
 pub mod event;
 pub mod input;
+#[cfg(feature = "ipc")]
+pub mod ipc;
 pub mod invariants;
 pub mod layout;
+pub mod outbox;
+pub mod saved_layout;
+pub mod scrolling;
 pub mod state;
 pub mod window;
 pub mod workspace;
@@ -47,24 +54,30 @@ pub use window::WindowId;
 pub use workspace::WorkspaceId;
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use indexmap::IndexMap;
 use tracing::{debug, error, info, warn};
 
 use config::Config;
+use decoration::Header;
 use input::{
-    FocusTarget, InputManager, LayoutCmd, MoveTarget, ResizeDirection, SplitCmd, Toggle,
-    WorkspaceTarget,
+    ColumnCmd, FocusTarget, GapCmd, GapScope, InputManager, LayoutCmd, MasterCmd, MoveTarget,
+    ResizeAmount, ResizeDirection, ResizeOp, SplitCmd, Toggle, WorkspaceTarget,
 };
-use layout::LayoutMode;
+use layout::{Direction, LayoutMode, LayoutNode, SplitDirection};
+use outbox::Outbox;
+use saved_layout::LayoutSpec;
 use state::{FocusState, GrabOperation, GrabbedWindow, Output, ResizeEdges, State};
-use window::{Window, WindowState};
+use window::{SizeHints, Window, WindowState};
 use workspace::Workspace;
 
 /// The protocol-agnostic window manager engine.
 ///
 /// Owns all WM state. Backends drive it via [`handle_event`](Core::handle_event)
 /// and [`exec`](Core::exec), then apply the returned [`CoreAction`]s.
+/// Each batch is coalesced and carries only what changed since the last
+/// one (see [`outbox`]).
 pub struct Core {
     /// All window-manager state
     pub state: State,
@@ -72,6 +85,8 @@ pub struct Core {
     pub input_manager: InputManager,
     /// Scratchpad-visible set (windows currently shown from scratchpad)
     scratchpad_visible: Vec<WindowId>,
+    /// What has been sent to the backend, to send only changes
+    outbox: Outbox,
     /// Monotonic window ID counter
     next_wid: u64,
     /// Exit requested
@@ -90,6 +105,7 @@ impl Core {
             state,
             input_manager,
             scratchpad_visible: Vec::new(),
+            outbox: Outbox::new(),
             next_wid: 1,
             should_exit: false,
         }
@@ -126,6 +142,8 @@ impl Core {
                 new_geometry_hint,
             } => self.on_window_commit(id, new_geometry_hint),
 
+            CoreEvent::WindowSizeHints { id, hints } => self.on_window_size_hints(id, hints),
+
             CoreEvent::FocusRequested { id } => self.on_focus_requested(id),
 
             CoreEvent::OutputAdded {
@@ -150,7 +168,7 @@ impl Core {
             warn!("Invariant violation after handle_event: {}", e);
         }
 
-        actions
+        self.outbox.flush(actions)
     }
 
     /// Execute a WM command (from keybinding, IPC, etc.). Returns actions.
@@ -163,13 +181,14 @@ impl Core {
             warn!("Invariant violation after exec: {}", e);
         }
 
-        actions
+        self.outbox.flush(actions)
     }
 
     /// Run one tick of the compositor loop. Returns actions needed
     /// (e.g., geometry updates from relayout). Returns empty if nothing changed.
     pub fn tick(&mut self) -> Vec<CoreAction> {
-        self.on_tick()
+        let actions = self.on_tick();
+        self.outbox.flush(actions)
     }
 
     // ── Event handlers ───────────────────────────────────────────────
@@ -196,15 +215,28 @@ impl Core {
         if should_float {
             window.state.insert(WindowState::FLOATING);
         }
+        let border = &self.state.config.border;
+        window.border = if should_float {
+            border.floating_style
+        } else {
+            border.style
+        };
 
         let added_id = self.state.add_window(window);
-        self.state.focus_window(added_id);
 
         // Produce actions
         let mut actions = Vec::new();
-        actions.push(CoreAction::SetFocus {
-            id: Some(added_id),
-        });
+        let workspace = self.state.windows.get(&added_id).and_then(|w| w.workspace);
+        let focused_workspace = self.state.focus.focused_workspace;
+        if focused_workspace.is_none() || workspace == focused_workspace {
+            self.state.focus_window(added_id);
+            actions.push(CoreAction::SetFocus {
+                id: Some(added_id),
+            });
+        } else {
+            // Swallowed by a placeholder on a workspace that isn't shown.
+            self.update_window_visibility();
+        }
 
         // Relayout and emit geometry actions
         actions.extend(self.relayout_actions());
@@ -215,6 +247,7 @@ impl Core {
         let mut actions = Vec::new();
 
         if self.state.remove_window(id).is_some() {
+            self.outbox.forget(id);
             // Report new focus
             actions.push(CoreAction::SetFocus {
                 id: self.state.focus.focused_window,
@@ -247,10 +280,31 @@ impl Core {
         Vec::new()
     }
 
+    fn on_window_size_hints(&mut self, id: WindowId, hints: SizeHints) -> Vec<CoreAction> {
+        let Some(window) = self.state.windows.get_mut(&id) else {
+            return Vec::new();
+        };
+        window.size_hints = hints;
+        if window.is_tiled() {
+            self.relayout_actions()
+        } else {
+            Vec::new()
+        }
+    }
+
     fn on_focus_requested(&mut self, id: WindowId) -> Vec<CoreAction> {
         if self.state.windows.contains_key(&id) {
             self.state.focus_window(id);
-            vec![CoreAction::SetFocus { id: Some(id) }]
+            let mut actions = vec![CoreAction::SetFocus { id: Some(id) }];
+            // Tab and stack headers show which tab holds focus.
+            let has_headers = self
+                .state
+                .focused_workspace()
+                .is_some_and(|ws| !ws.layout.tab_containers.is_empty());
+            if has_headers {
+                actions.extend(self.relayout_actions());
+            }
+            actions
         } else {
             Vec::new()
         }
@@ -272,20 +326,20 @@ impl Core {
             active_workspace: None,
         };
         self.state.outputs.insert(id, output);
-
-        // Set workspace geometry to first output
-        for ws in self.state.workspaces.values_mut() {
-            if ws.geometry == Geometry::default() {
-                ws.set_geometry(geometry);
-            }
-        }
-
+        self.state.assign_workspace_outputs();
+        // The backend may have dropped what it had when the output went.
+        self.outbox.clear();
+        #[cfg(feature = "multi-output")]
+        self.state.populate_output(id);
+        self.update_window_visibility();
         self.relayout_actions()
     }
 
     fn on_output_removed(&mut self, id: u64) -> Vec<CoreAction> {
         self.state.outputs.shift_remove(&id);
-        Vec::new()
+        self.state.assign_workspace_outputs();
+        self.update_window_visibility();
+        self.relayout_actions()
     }
 
     fn on_pointer_motion(&mut self, x: f64, y: f64) -> Vec<CoreAction> {
@@ -427,7 +481,7 @@ impl Core {
                 actions.push(CoreAction::SpawnProcess { command: cmd });
             }
             Command::Kill => {
-                if let Some(wid) = self.state.focus.focused_window {
+                for wid in self.state.selected_windows() {
                     actions.push(CoreAction::RequestClose { id: wid });
                 }
             }
@@ -435,7 +489,7 @@ impl Core {
                 actions.extend(self.cmd_focus(target));
             }
             Command::Move(target) => {
-                actions.extend(self.cmd_move(target));
+                actions.extend(self.cmd_move(&target));
             }
             Command::Floating(toggle) => {
                 actions.extend(self.cmd_floating(toggle));
@@ -446,11 +500,20 @@ impl Core {
             Command::Sticky(toggle) => {
                 self.cmd_sticky(toggle);
             }
-            Command::Split(_cmd) => {
-                // TODO: Set split direction for current container
+            Command::Split(cmd) => {
+                actions.extend(self.cmd_split(cmd));
             }
-            Command::Layout(_cmd) => {
-                // TODO: Set layout mode for current container
+            Command::Layout(cmd) => {
+                actions.extend(self.cmd_layout(cmd));
+            }
+            Command::Master(cmd) => {
+                actions.extend(self.cmd_master(cmd));
+            }
+            Command::Column(cmd) => {
+                actions.extend(self.cmd_column(cmd));
+            }
+            Command::AppendLayout(path) => {
+                actions.extend(self.cmd_append_layout(&path));
             }
             Command::Workspace(target) => {
                 actions.extend(self.cmd_switch_workspace(target));
@@ -458,6 +521,9 @@ impl Core {
             Command::MoveToWorkspace(target) => {
                 actions.extend(self.cmd_move_to_workspace(target));
             }
+            Command::RenameWorkspace { from, to } => {
+                actions.extend(self.cmd_rename_workspace(from.as_deref(), &to));
+            }
             Command::ScratchpadShow => {
                 actions.extend(self.cmd_toggle_scratchpad());
             }
@@ -467,11 +533,11 @@ impl Core {
                     actions.extend(self.relayout_actions());
                 }
             }
-            Command::Mark(mark) => {
-                if let Some(wid) = self.state.focus.focused_window {
-                    self.state.set_mark(mark, wid);
-                }
-            }
+            Command::Mark(mark) => match self.state.focus.selection() {
+                Some(LayoutNode::Container(id)) => self.state.set_container_mark(mark, id),
+                Some(LayoutNode::Window(wid)) => self.state.set_mark(mark, wid),
+                None => {}
+            },
             Command::GotoMark(mark) => {
                 let prev_ws = self.state.focus.focused_workspace;
                 self.state.goto_mark(&mark);
@@ -487,8 +553,10 @@ impl Core {
             Command::Unmark(mark) => {
                 if let Some(m) = mark {
                     self.state.marks.remove(&m);
+                    self.state.container_marks.remove(&m);
                 } else {
                     self.state.marks.clear();
+                    self.state.container_marks.clear();
                 }
             }
             Command::Reload => {
@@ -505,10 +573,11 @@ impl Core {
                 self.input_manager.set_mode(&mode_name);
             }
             Command::Resize(direction, amount) => {
-                // TODO: implement container resizing
-                let _ = (direction, amount);
+                actions.extend(self.cmd_resize(direction, amount));
+            }
+            Command::Gaps(cmd, scope) => {
+                actions.extend(self.cmd_gaps(&cmd, scope));
             }
-            Command::Gaps(_gap_cmd) => {}
             Command::Bar(_bar_cmd) => {}
             Command::Unknown(cmd) => {
                 warn!("Unknown command: {}", cmd);
@@ -519,20 +588,518 @@ impl Core {
     }
 
     fn cmd_focus(&mut self, target: FocusTarget) -> Vec<CoreAction> {
-        // TODO: full directional focus navigation using layout tree
-        debug!("Focus target: {:?}", target);
-        Vec::new()
+        let columns_only = matches!(target, FocusTarget::ColumnLeft | FocusTarget::ColumnRight);
+        let direction = match target {
+            FocusTarget::Left | FocusTarget::ColumnLeft => Direction::Left,
+            FocusTarget::Right | FocusTarget::ColumnRight => Direction::Right,
+            FocusTarget::Up => Direction::Up,
+            FocusTarget::Down => Direction::Down,
+            FocusTarget::Parent => {
+                self.cmd_focus_parent();
+                return Vec::new();
+            }
+            FocusTarget::Child => {
+                self.cmd_focus_child();
+                return Vec::new();
+            }
+            FocusTarget::Output(ref output) => return self.cmd_focus_output(output),
+            _ => {
+                debug!("Focus target: {:?}", target);
+                return Vec::new();
+            }
+        };
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let Some(node) = self.state.focus.selection() else {
+            return Vec::new();
+        };
+
+        let wrapping = self.state.config.general.focus_wrapping;
+        let Some(ws) = self.state.window_workspace_mut(wid) else {
+            return Vec::new();
+        };
+        let target = if let Some(scrolling) = &mut ws.scrolling {
+            scrolling.focus_window(wid);
+            scrolling.focus_direction(direction)
+        } else if columns_only {
+            None
+        } else {
+            ws.layout.focus_window(wid);
+            ws.layout.neighbour(node, direction, wrapping)
+        };
+        let Some(target) = target else {
+            return Vec::new();
+        };
+
+        self.state.focus_window(target);
+        let mut actions = vec![CoreAction::SetFocus { id: Some(target) }];
+        // Focusing into a tabbed/stacked container changes which child is shown.
+        actions.extend(self.relayout_actions());
+        actions
     }
 
-    fn cmd_move(&mut self, _target: MoveTarget) -> Vec<CoreAction> {
-        // TODO: implement window movement
-        Vec::new()
+    /// Focus the workspace shown on another output.
+    fn cmd_focus_output(&mut self, target: &str) -> Vec<CoreAction> {
+        let workspace_id = self
+            .resolve_output(target)
+            .and_then(|output| output.active_workspace);
+        match workspace_id {
+            Some(id) if self.state.focus.focused_workspace != Some(id) => self.show_workspace(id),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Select the container enclosing the current selection.
+    fn cmd_focus_parent(&mut self) {
+        let Some(node) = self.state.focus.selection() else {
+            return;
+        };
+        let parent = self
+            .state
+            .focused_window()
+            .and_then(|window| self.state.workspaces.get(&window.workspace?))
+            .and_then(|ws| ws.layout.parent_of(node));
+        if let Some(parent) = parent {
+            self.state.focus.focused_container = Some(parent);
+        }
+    }
+
+    /// Descend one level from the selected container towards the focused window.
+    fn cmd_focus_child(&mut self) {
+        let Some(container_id) = self.state.focus.focused_container else {
+            return;
+        };
+        let child = self
+            .state
+            .container_workspace(container_id)
+            .and_then(|ws| ws.layout.containers.get(container_id))
+            .and_then(|container| container.focused().copied());
+        self.state.focus.focused_container = match child {
+            Some(LayoutNode::Container(id)) => Some(id),
+            _ => None,
+        };
+    }
+
+    fn cmd_move(&mut self, target: &MoveTarget) -> Vec<CoreAction> {
+        let direction = match target {
+            MoveTarget::Left => Direction::Left,
+            MoveTarget::Right => Direction::Right,
+            MoveTarget::Up => Direction::Up,
+            MoveTarget::Down => Direction::Down,
+            MoveTarget::Output(output) => return self.cmd_move_to_output(output),
+            _ => {
+                debug!("Move target: {:?}", target);
+                return Vec::new();
+            }
+        };
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let Some(node) = self.state.focus.selection() else {
+            return Vec::new();
+        };
+        let Some(ws) = self.state.window_workspace_mut(wid) else {
+            return Vec::new();
+        };
+
+        let moved = if let Some(scrolling) = &mut ws.scrolling {
+            scrolling.focus_window(wid) && scrolling.move_direction(direction)
+        } else if ws.layout.move_node(node, direction) {
+            ws.layout.focus_window(wid);
+            true
+        } else {
+            false
+        };
+
+        if moved {
+            self.state.layout_dirty = true;
+            self.relayout_actions()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn cmd_split(&mut self, cmd: SplitCmd) -> Vec<CoreAction> {
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let Some(ws) = self.state.window_workspace_mut(wid) else {
+            return Vec::new();
+        };
+
+        let changed = match cmd {
+            SplitCmd::Horizontal => ws.layout.split(wid, SplitDirection::Horizontal),
+            SplitCmd::Vertical => ws.layout.split(wid, SplitDirection::Vertical),
+            SplitCmd::Toggle => match ws.layout.direction_of(wid) {
+                Some(direction) => ws.layout.split(wid, direction.toggle()),
+                None => false,
+            },
+            SplitCmd::None => ws.layout.unsplit(wid),
+        };
+
+        if changed {
+            self.state.layout_dirty = true;
+            self.relayout_actions()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn cmd_layout(&mut self, cmd: LayoutCmd) -> Vec<CoreAction> {
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let Some(ws) = self
+            .state
+            .windows
+            .get(&wid)
+            .and_then(|window| window.workspace)
+            .and_then(|id| self.state.workspaces.get_mut(&id))
+        else {
+            return Vec::new();
+        };
+
+        // `layout scrolling` swaps the whole workspace over to columns; any
+        // other layout turns a scrolling workspace back into a tree first.
+        let mut actions = Vec::new();
+        let scrolling = cmd == LayoutCmd::Scrolling;
+        if scrolling || ws.is_scrolling() {
+            let offscreen: Vec<WindowId> = ws
+                .tiled_windows
+                .iter()
+                .copied()
+                .filter(|&id| !ws.window_in_viewport(id))
+                .collect();
+            ws.set_scrolling(scrolling, &self.state.config);
+            if scrolling {
+                self.state.layout_dirty = true;
+                return self.relayout_actions();
+            }
+            for id in offscreen {
+                if let Some(window) = self.state.windows.get_mut(&id) {
+                    window.state.remove(WindowState::HIDDEN);
+                    actions.push(CoreAction::SetVisible { id, visible: true });
+                }
+            }
+        }
+
+        let master_ratio = self.state.config.general.master_ratio;
+        let master_count = self.state.config.general.master_count;
+        let Some(container) = ws
+            .layout
+            .container_of(wid)
+            .and_then(|id| ws.layout.containers.get_mut(id))
+        else {
+            return actions;
+        };
+
+        match cmd {
+            LayoutCmd::Default => container.layout = LayoutMode::Split,
+            LayoutCmd::Tabbed => container.layout = LayoutMode::Tabbed,
+            LayoutCmd::Stacked => container.layout = LayoutMode::Stacked,
+            LayoutCmd::Dwindle => container.layout = LayoutMode::Dwindle,
+            LayoutCmd::Grid => container.layout = LayoutMode::Grid,
+            LayoutCmd::SplitH => container.set_split(SplitDirection::Horizontal),
+            LayoutCmd::SplitV => container.set_split(SplitDirection::Vertical),
+            LayoutCmd::Toggle => container.cycle_layout(),
+            LayoutCmd::ToggleSplit => container.toggle_split_layout(),
+            LayoutCmd::ToggleAll => container.cycle_all_layouts(),
+            LayoutCmd::MasterStack => {
+                if container.layout != LayoutMode::MasterStack {
+                    container.layout = LayoutMode::MasterStack;
+                    container.set_master_ratio(master_ratio);
+                    container.master_count = master_count;
+                }
+            }
+            LayoutCmd::Scrolling => {}
+        }
+
+        self.state.layout_dirty = true;
+        actions.extend(self.relayout_actions());
+        actions
+    }
+
+    fn cmd_master(&mut self, cmd: MasterCmd) -> Vec<CoreAction> {
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let Some(ws) = self.state.window_workspace_mut(wid) else {
+            return Vec::new();
+        };
+        let Some((container_id, _)) = ws.layout.master_stack_of(wid) else {
+            return Vec::new();
+        };
+
+        match cmd {
+            MasterCmd::Swap => {
+                if !ws.layout.swap_master(wid) {
+                    return Vec::new();
+                }
+            }
+            MasterCmd::Ratio(adjust) => {
+                let Some(container) = ws.layout.containers.get_mut(container_id) else {
+                    return Vec::new();
+                };
+                let percent = adjust.apply((container.master_ratio * 100.0).round() as i32);
+                container.set_master_ratio(f64::from(percent) / 100.0);
+            }
+            MasterCmd::Count(adjust) => {
+                let Some(container) = ws.layout.containers.get_mut(container_id) else {
+                    return Vec::new();
+                };
+                let current = i32::try_from(container.master_count).unwrap_or(i32::MAX);
+                let count = adjust.apply(current);
+                container.master_count = count.max(0) as usize;
+            }
+        }
+
+        self.state.layout_dirty = true;
+        self.relayout_actions()
+    }
+
+    fn cmd_column(&mut self, cmd: ColumnCmd) -> Vec<CoreAction> {
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let Some(scrolling) = self
+            .state
+            .window_workspace_mut(wid)
+            .and_then(|ws| ws.scrolling.as_mut())
+        else {
+            return Vec::new();
+        };
+        if !scrolling.focus_window(wid) {
+            return Vec::new();
+        }
+
+        let changed = match cmd {
+            ColumnCmd::CycleWidth => scrolling.cycle_column_width(),
+            ColumnCmd::Consume => scrolling.consume_into_column(),
+            ColumnCmd::Expel => scrolling.expel_from_column(),
+        };
+
+        if changed {
+            self.state.layout_dirty = true;
+            self.relayout_actions()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn cmd_append_layout(&mut self, path: &str) -> Vec<CoreAction> {
+        let spec = match LayoutSpec::load(Path::new(path)) {
+            Ok(spec) => spec,
+            Err(err) => {
+                warn!("append_layout: {:#}", err);
+                return Vec::new();
+            }
+        };
+        let Some(ws) = self
+            .state
+            .focus
+            .focused_workspace
+            .or_else(|| self.state.workspaces.keys().next().copied())
+            .and_then(|id| self.state.workspaces.get_mut(&id))
+        else {
+            return Vec::new();
+        };
+        if ws.is_scrolling() {
+            warn!("append_layout: not supported on a scrolling workspace");
+            return Vec::new();
+        }
+
+        ws.append_layout(spec.build(&self.state.config));
+        self.state.layout_dirty = true;
+        self.relayout_actions()
+    }
+
+    /// `gaps ... current` overrides the focused workspace's gaps; `gaps ...
+    /// all` changes the global setting and drops every override.
+    fn cmd_gaps(&mut self, cmd: &GapCmd, scope: GapScope) -> Vec<CoreAction> {
+        match scope {
+            GapScope::All => {
+                cmd.apply(&mut self.state.config.gaps);
+                for ws in self.state.workspaces.values_mut() {
+                    ws.gaps = None;
+                }
+            }
+            GapScope::Current => {
+                let Some(ws) = self
+                    .state
+                    .focus
+                    .focused_workspace
+                    .and_then(|id| self.state.workspaces.get_mut(&id))
+                else {
+                    return Vec::new();
+                };
+                let mut gaps = ws.gaps(&self.state.config);
+                cmd.apply(&mut gaps);
+                ws.gaps = Some(gaps);
+            }
+        }
+
+        self.state.layout_dirty = true;
+        self.relayout_actions()
+    }
+
+    fn cmd_resize(&mut self, direction: ResizeDirection, amount: ResizeAmount) -> Vec<CoreAction> {
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        let floating = self
+            .state
+            .windows
+            .get(&wid)
+            .is_some_and(|w| w.state.contains(WindowState::FLOATING));
+        if floating {
+            return self.resize_floating(wid, direction, amount);
+        }
+
+        let (axis, edge) = match direction {
+            ResizeDirection::Width(_) => (SplitDirection::Horizontal, None),
+            ResizeDirection::Height(_) => (SplitDirection::Vertical, None),
+            ResizeDirection::Left(_) => (SplitDirection::Horizontal, Some(Direction::Left)),
+            ResizeDirection::Right(_) => (SplitDirection::Horizontal, Some(Direction::Right)),
+            ResizeDirection::Up(_) => (SplitDirection::Vertical, Some(Direction::Up)),
+            ResizeDirection::Down(_) => (SplitDirection::Vertical, Some(Direction::Down)),
+        };
+        let Some(ws) = self.state.window_workspace_mut(wid) else {
+            return Vec::new();
+        };
+        let Some((container_id, index, neighbour)) = ws.layout.resize_target(wid, axis, edge)
+        else {
+            return Vec::new();
+        };
+        let Some(container) = ws.layout.containers.get_mut(container_id) else {
+            return Vec::new();
+        };
+
+        // Pixel amounts become a share of the space the split divides; a
+        // master–stack container divides it once, between its two areas.
+        let master_stack = container.layout == LayoutMode::MasterStack;
+        let gaps = if master_stack {
+            container.gap
+        } else {
+            container.gap * (container.children.len() as u32 - 1)
+        };
+        let length = match axis {
+            SplitDirection::Horizontal => container.geometry.width,
+            SplitDirection::Vertical => container.geometry.height,
+        }
+        .saturating_sub(gaps);
+        let share = match amount {
+            ResizeAmount::Px(_) if length == 0 => return Vec::new(),
+            ResizeAmount::Px(px) => f64::from(px) / f64::from(length),
+            ResizeAmount::Ppt(ppt) => f64::from(ppt) / 100.0,
+        };
+        if master_stack {
+            // Growing a master widens the master area, growing a stack
+            // window narrows it.
+            let in_master = index < container.master_count;
+            let towards_stack = if in_master { share } else { -share };
+            let ratio = match direction.op() {
+                ResizeOp::Grow => container.master_ratio + towards_stack,
+                ResizeOp::Shrink => container.master_ratio - towards_stack,
+                ResizeOp::Set if in_master => share,
+                ResizeOp::Set => 1.0 - share,
+            };
+            container.set_master_ratio(ratio);
+        } else {
+            match direction.op() {
+                ResizeOp::Grow => container.resize_towards(index, neighbour, share),
+                ResizeOp::Shrink => container.resize_towards(index, neighbour, -share),
+                ResizeOp::Set => container.pin_ratio(index, share),
+            }
+        }
+
+        self.state.layout_dirty = true;
+        self.relayout_actions()
+    }
+
+    fn resize_floating(
+        &mut self,
+        wid: WindowId,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+    ) -> Vec<CoreAction> {
+        let area = self
+            .state
+            .focused_workspace()
+            .map(|ws| ws.geometry)
+            .unwrap_or_default();
+        let Some(window) = self.state.windows.get_mut(&wid) else {
+            return Vec::new();
+        };
+
+        let old = window.geometry;
+        let horizontal = matches!(
+            direction,
+            ResizeDirection::Width(_) | ResizeDirection::Left(_) | ResizeDirection::Right(_)
+        );
+        let (current, reference) = if horizontal {
+            (old.width, area.width)
+        } else {
+            (old.height, area.height)
+        };
+        let amount = match amount {
+            ResizeAmount::Px(px) => px,
+            ResizeAmount::Ppt(ppt) => (f64::from(reference) * f64::from(ppt) / 100.0) as i32,
+        };
+        let size = match direction.op() {
+            ResizeOp::Grow => current as i32 + amount,
+            ResizeOp::Shrink => current as i32 - amount,
+            ResizeOp::Set => amount,
+        }
+        .max(1) as u32;
+
+        let mut geometry = old;
+        if horizontal {
+            geometry.width = size;
+        } else {
+            geometry.height = size;
+        }
+        window.set_geometry(geometry);
+
+        // Resizing from the left/top edge keeps the opposite edge anchored.
+        let g = &mut window.geometry;
+        match direction {
+            ResizeDirection::Left(_) => g.x = old.x + old.width as i32 - g.width as i32,
+            ResizeDirection::Up(_) => g.y = old.y + old.height as i32 - g.height as i32,
+            _ => {}
+        }
+
+        let g = window.geometry;
+        vec![CoreAction::SetWindowGeometry {
+            id: wid,
+            x: g.x,
+            y: g.y,
+            w: g.width,
+            h: g.height,
+        }]
     }
 
     fn cmd_floating(&mut self, toggle: Toggle) -> Vec<CoreAction> {
         let mut actions = Vec::new();
 
-        if let Some(wid) = self.state.focus.focused_window {
+        // A selected container floats or tiles as a whole, following the
+        // window that holds focus inside it.
+        let toggle = match toggle {
+            Toggle::Switch if self.state.focus.focused_container.is_some() => {
+                if self
+                    .state
+                    .focused_window()
+                    .is_some_and(|w| w.state.contains(WindowState::FLOATING))
+                {
+                    Toggle::Disable
+                } else {
+                    Toggle::Enable
+                }
+            }
+            toggle => toggle,
+        };
+
+        for wid in self.state.selected_windows() {
             if let Some(window) = self.state.windows.get_mut(&wid) {
                 let was_floating = window.state.contains(WindowState::FLOATING);
                 match toggle {
@@ -554,34 +1121,46 @@ impl Core {
                                 ws.float_window(wid);
                             }
                         } else if let Some(ws) = self.state.workspaces.get_mut(&ws_id) {
-                            ws.tile_window(wid, &self.state.config);
+                            let placement = self.state.config.placement_for(window);
+                            ws.tile_window(wid, placement, &self.state.config);
                         }
                     }
                     self.state.layout_dirty = true;
-                    actions.extend(self.relayout_actions());
                 }
             }
         }
 
+        if self.state.layout_dirty {
+            if self
+                .state
+                .focus
+                .focused_container
+                .is_some_and(|id| self.state.container_workspace(id).is_none())
+            {
+                self.state.focus.focused_container = None;
+            }
+            actions.extend(self.relayout_actions());
+        }
         actions
     }
 
     fn cmd_fullscreen(&mut self, toggle: Toggle) -> Vec<CoreAction> {
         if let Some(wid) = self.state.focus.focused_window {
+            // The window's own output, or a default until the backend
+            // reports one via OutputAdded.
+            let output_geo = self
+                .state
+                .windows
+                .get(&wid)
+                .and_then(|window| self.state.workspace_output(window.workspace?))
+                .or_else(|| self.state.outputs.values().next())
+                .map_or(Geometry::new(0, 0, 1920, 1080), |o| o.geometry);
             if let Some(window) = self.state.windows.get_mut(&wid) {
                 let enable = match toggle {
                     Toggle::Enable => true,
                     Toggle::Disable => false,
                     Toggle::Switch => !window.state.contains(WindowState::FULLSCREEN),
                 };
-                // Default output geometry — backend should provide real one via OutputAdded
-                let output_geo = self
-                    .state
-                    .outputs
-                    .values()
-                    .next()
-                    .map(|o| o.geometry)
-                    .unwrap_or(Geometry::new(0, 0, 1920, 1080));
                 window.set_fullscreen(enable, output_geo);
                 let g = window.geometry;
                 return vec![CoreAction::SetWindowGeometry {
@@ -615,37 +1194,96 @@ impl Core {
     }
 
     fn cmd_switch_workspace(&mut self, target: WorkspaceTarget) -> Vec<CoreAction> {
-        let workspace_id = self.resolve_workspace_target(&target);
+        let workspace_id = self
+            .resolve_workspace_target(&target)
+            .map(|id| self.auto_back_and_forth(id));
 
-        if let Some(id) = workspace_id {
-            self.state.switch_workspace(id);
-            self.update_window_visibility();
-            let mut actions = vec![CoreAction::WorkspaceChanged {
-                active: Some(id),
-            }];
-            actions.push(CoreAction::SetFocus {
-                id: self.state.focus.focused_window,
-            });
-            actions.extend(self.relayout_actions());
-            actions
-        } else {
-            Vec::new()
-        }
+        workspace_id.map_or_else(Vec::new, |id| self.show_workspace(id))
+    }
+
+    /// Focus `workspace_id`, showing it on its output.
+    fn show_workspace(&mut self, workspace_id: WorkspaceId) -> Vec<CoreAction> {
+        self.state.switch_workspace(workspace_id);
+        self.update_window_visibility();
+        let mut actions = vec![CoreAction::WorkspaceChanged {
+            active: Some(workspace_id),
+        }];
+        actions.push(CoreAction::SetFocus {
+            id: self.state.focus.focused_window,
+        });
+        actions.extend(self.relayout_actions());
+        actions
     }
 
     fn cmd_move_to_workspace(&mut self, target: WorkspaceTarget) -> Vec<CoreAction> {
-        let Some(wid) = self.state.focus.focused_window else {
+        // Resolving the target may create it, so only with something to move.
+        if self.state.focus.focused_window.is_none() {
             return Vec::new();
-        };
+        }
         let Some(ws_id) = self.resolve_workspace_target(&target) else {
             return Vec::new();
         };
+        let ws_id = self.auto_back_and_forth(ws_id);
+        self.move_selection_to_workspace(ws_id)
+    }
+
+    /// Move the selection to the workspace shown on another output.
+    fn cmd_move_to_output(&mut self, target: &str) -> Vec<CoreAction> {
+        let workspace_id = self
+            .resolve_output(target)
+            .and_then(|output| output.active_workspace);
+        match workspace_id {
+            Some(id) if self.state.focus.focused_workspace != Some(id) => {
+                self.move_selection_to_workspace(id)
+            }
+            _ => Vec::new(),
+        }
+    }
 
-        self.state.move_window_to_workspace(wid, ws_id);
+    /// Move the focused window, or the selected container, to `ws_id`.
+    fn move_selection_to_workspace(&mut self, ws_id: WorkspaceId) -> Vec<CoreAction> {
+        let Some(wid) = self.state.focus.focused_window else {
+            return Vec::new();
+        };
+        match self.state.focus.focused_container {
+            Some(container_id) => self.state.move_container_to_workspace(container_id, ws_id),
+            None => self.state.move_window_to_workspace(wid, ws_id),
+        }
         self.update_window_visibility();
         self.relayout_actions()
     }
 
+    /// Rename the workspace called `from`, or the focused one, to `to`,
+    /// unless another workspace already has that name.
+    fn cmd_rename_workspace(&mut self, from: Option<&str>, to: &str) -> Vec<CoreAction> {
+        let workspace_id = match from {
+            Some(name) => self
+                .state
+                .workspaces
+                .values()
+                .find(|ws| ws.name == name)
+                .map(|ws| ws.id),
+            None => self.state.focus.focused_workspace,
+        };
+        let Some(id) = workspace_id else {
+            warn!("rename workspace: no workspace called {:?}", from.unwrap_or_default());
+            return Vec::new();
+        };
+        let Some(old_name) = self.state.rename_workspace(id, to) else {
+            warn!("rename workspace: {:?} is already taken", to);
+            return Vec::new();
+        };
+
+        let mut actions = vec![CoreAction::WorkspaceRenamed {
+            id,
+            old_name,
+            name: to.to_string(),
+        }];
+        // Its config entry, if any, now goes by the new name.
+        actions.extend(self.relayout_actions());
+        actions
+    }
+
     fn cmd_toggle_scratchpad(&mut self) -> Vec<CoreAction> {
         let mut actions = Vec::new();
 
@@ -665,10 +1303,10 @@ impl Core {
 
     // ── Helpers ──────────────────────────────────────────────────────
 
-    fn resolve_workspace_target(&self, target: &WorkspaceTarget) -> Option<WorkspaceId> {
+    fn resolve_workspace_target(&mut self, target: &WorkspaceTarget) -> Option<WorkspaceId> {
         match target {
             WorkspaceTarget::Next | WorkspaceTarget::NextOnOutput => {
-                let keys: Vec<_> = self.state.workspaces.keys().copied().collect();
+                let keys = self.workspace_cycle(target == &WorkspaceTarget::NextOnOutput);
                 if let Some(current) = self.state.focus.focused_workspace {
                     let idx = keys.iter().position(|&id| id == current).unwrap_or(0);
                     keys.get((idx + 1) % keys.len()).copied()
@@ -677,7 +1315,7 @@ impl Core {
                 }
             }
             WorkspaceTarget::Prev | WorkspaceTarget::PrevOnOutput => {
-                let keys: Vec<_> = self.state.workspaces.keys().copied().collect();
+                let keys = self.workspace_cycle(target == &WorkspaceTarget::PrevOnOutput);
                 if let Some(current) = self.state.focus.focused_workspace {
                     let idx = keys.iter().position(|&id| id == current).unwrap_or(0);
                     let new_idx = if idx == 0 {
@@ -690,32 +1328,92 @@ impl Core {
                     keys.last().copied()
                 }
             }
-            WorkspaceTarget::Number(num) => self
-                .state
-                .workspaces
-                .keys()
-                .nth((*num as usize).saturating_sub(1))
-                .copied(),
-            WorkspaceTarget::Name(ref name) => self
-                .state
-                .workspaces
-                .iter()
-                .find(|(_, ws)| ws.name == *name)
-                .map(|(id, _)| *id),
-            WorkspaceTarget::BackAndForth => None,
+            WorkspaceTarget::Number(num) => Some(self.state.workspace_numbered(*num)),
+            WorkspaceTarget::Name(ref name) => Some(self.state.workspace_named(name)),
+            WorkspaceTarget::BackAndForth => {
+                let name = self.state.focus.previous_workspace.clone()?;
+                Some(self.state.workspace_named(&name))
+            }
+        }
+    }
+
+    /// Workspaces `next` and `prev` cycle through: those on the focused
+    /// output, or all of them.
+    fn workspace_cycle(&self, on_output: bool) -> Vec<WorkspaceId> {
+        match self.state.focused_output() {
+            Some(output) if on_output => output.workspaces.clone(),
+            _ => self.state.workspaces.keys().copied().collect(),
+        }
+    }
+
+    /// The output called `target`, or else the nearest one in direction
+    /// `target` (`left`, `right`, `up` or `down`) from the focused output.
+    fn resolve_output(&self, target: &str) -> Option<&Output> {
+        let outputs = &self.state.outputs;
+        if let Some(output) = outputs.values().find(|output| output.name == target) {
+            return Some(output);
+        }
+        let (x, y) = self.state.focused_output()?.geometry.center();
+        let target = target.to_lowercase();
+        outputs
+            .values()
+            .filter(|output| {
+                let (ox, oy) = output.geometry.center();
+                match target.as_str() {
+                    "left" => ox < x,
+                    "right" => ox > x,
+                    "up" => oy < y,
+                    "down" => oy > y,
+                    _ => false,
+                }
+            })
+            .min_by_key(|output| {
+                let (ox, oy) = output.geometry.center();
+                (ox - x).abs() + (oy - y).abs()
+            })
+    }
+
+    /// With `workspace_auto_back_and_forth`, naming the focused workspace
+    /// means the previously focused one.
+    fn auto_back_and_forth(&mut self, workspace_id: WorkspaceId) -> WorkspaceId {
+        if !self.state.config.general.workspace_auto_back_and_forth
+            || self.state.focus.focused_workspace != Some(workspace_id)
+        {
+            return workspace_id;
+        }
+        match self.state.focus.previous_workspace.clone() {
+            Some(name) => self.state.workspace_named(&name),
+            None => workspace_id,
         }
     }
 
-    /// Relayout current workspace and produce geometry actions for all tiled windows.
+    /// Relayout the visible workspaces and produce geometry actions for
+    /// their tiled windows.
     fn relayout_actions(&mut self) -> Vec<CoreAction> {
-        let ws_id = match self.state.focus.focused_workspace {
-            Some(id) => id,
-            None => return Vec::new(),
-        };
+        let visible = self.state.visible_workspaces();
+        if visible.is_empty() {
+            return Vec::new();
+        }
+
+        let mut actions = Vec::new();
+        let mut headers = Vec::new();
+        for ws_id in visible {
+            actions.extend(self.workspace_layout_actions(ws_id, &mut headers));
+        }
+        actions.push(CoreAction::SetDecorations { headers });
 
-        let outer_gap = self.state.config.gaps.outer;
+        self.state.layout_dirty = false;
+        actions
+    }
+
+    /// Relayout one workspace, adding its tab and stack headers to `headers`.
+    fn workspace_layout_actions(
+        &mut self,
+        ws_id: WorkspaceId,
+        headers: &mut Vec<Header>,
+    ) -> Vec<CoreAction> {
         if let Some(workspace) = self.state.workspaces.get_mut(&ws_id) {
-            workspace.calculate_layout(outer_gap);
+            workspace.calculate_layout(&self.state.config, &self.state.windows);
         }
 
         let mut actions = Vec::new();
@@ -729,18 +1427,67 @@ impl Core {
                         w: geo.width,
                         h: geo.height,
                     });
+                    let window = self.state.windows.get(&wid);
+                    let width = window.map_or(0, Window::border_width);
+                    let borders = workspace.window_borders(wid, width, &self.state.config);
+                    let title_bar = window
+                        .map_or(0, |w| workspace.title_bar_height(w, &self.state.config));
+                    actions.push(CoreAction::SetWindowFrame {
+                        id: wid,
+                        frame: geo,
+                        client: borders.client_area(geo, title_bar),
+                    });
+                    actions.push(CoreAction::SetWindowBorder { id: wid, borders });
                 }
             }
+            for &id in &workspace.layout.unfit_windows {
+                debug!("{} does not fit its tiled space", id);
+                actions.push(CoreAction::WindowDoesNotFit { id });
+            }
+            headers.extend(workspace.headers(
+                &self.state.windows,
+                self.state.focus.focused_window,
+                &self.state.config,
+            ));
+        }
+        actions.extend(self.viewport_visibility_actions(ws_id));
+        actions
+    }
+
+    /// Hide tiled windows scrolled out of a scrolling workspace's viewport,
+    /// and show those scrolled back in, reporting each change.
+    fn viewport_visibility_actions(&mut self, ws_id: WorkspaceId) -> Vec<CoreAction> {
+        let Some(workspace) = self.state.workspaces.get(&ws_id) else {
+            return Vec::new();
+        };
+        if !workspace.is_scrolling() {
+            return Vec::new();
         }
 
-        self.state.layout_dirty = false;
+        let mut actions = Vec::new();
+        for &wid in &workspace.tiled_windows {
+            let Some(window) = self.state.windows.get_mut(&wid) else {
+                continue;
+            };
+            let visible = workspace.window_in_viewport(wid);
+            if visible == window.state.contains(WindowState::HIDDEN) {
+                window.state.set(WindowState::HIDDEN, !visible);
+                actions.push(CoreAction::SetVisible { id: wid, visible });
+            }
+        }
         actions
     }
 
+    /// Show the windows of each output's active workspace and hide the rest.
     fn update_window_visibility(&mut self) {
-        let current_ws_id = self.state.focus.focused_workspace;
+        let visible = self.state.visible_workspaces();
         for (window_id, window) in &mut self.state.windows {
-            let should_show = window.workspace == current_ws_id
+            let in_viewport = window
+                .workspace
+                .and_then(|id| self.state.workspaces.get(&id))
+                .map_or(true, |ws| ws.window_in_viewport(*window_id));
+            let on_visible = window.workspace.is_some_and(|id| visible.contains(&id));
+            let should_show = (on_visible && in_viewport)
                 || self.scratchpad_visible.contains(window_id)
                 || window.state.contains(WindowState::STICKY);
 
@@ -756,7 +1503,10 @@ impl Core {
     pub fn reload_config(&mut self, config: Config) {
         self.input_manager.load_bindings(&config.bindings);
         self.state.config = config;
+        self.state.create_configured_workspaces();
+        self.state.assign_workspace_outputs();
         self.state.layout_dirty = true;
+        self.outbox.clear();
     }
 
     /// Access the focused workspace ID.
@@ -0,0 +1,218 @@
+//! What the core has already told the backend.
+//!
+//! Every batch of actions returned from [`Core`](crate::Core) passes
+//! through an [`Outbox`]. Within a batch only the last action setting
+//! the same thing survives, and geometry, frames, borders and headers the
+//! backend already has are dropped, so clients are only configured when
+//! something about them actually changed. A window that does not fit is
+//! reported once, and again only after a layout in which it fit.
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use crate::decoration::Header;
+use crate::event::CoreAction;
+use crate::state::Geometry;
+use crate::window::{Borders, WindowId};
+
+/// The last geometry, frame and border sent for one window.
+#[derive(Debug, Clone, Copy, Default)]
+struct Sent {
+    geometry: Option<Geometry>,
+    frame: Option<(Geometry, Geometry)>,
+    borders: Option<Borders>,
+    /// Reported as not fitting since it last fit.
+    unfit: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Outbox {
+    windows: HashMap<WindowId, Sent>,
+    headers: Option<Vec<Header>>,
+}
+
+impl Outbox {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coalesce `actions` and drop those repeating what was already sent.
+    pub fn flush(&mut self, actions: Vec<CoreAction>) -> Vec<CoreAction> {
+        // Walk backwards so the last action for each key is the one kept.
+        let mut seen = HashSet::new();
+        let mut kept: Vec<CoreAction> = actions
+            .into_iter()
+            .rev()
+            .filter(|action| key(action).map_or(true, |key| seen.insert(key)))
+            .collect();
+        kept.reverse();
+        self.note_fitting(&kept);
+        kept.retain(|action| self.record(action));
+        kept
+    }
+
+    /// Clear the unfit mark of windows laid out in `actions` without being
+    /// reported, so that they are reported should they stop fitting again.
+    fn note_fitting(&mut self, actions: &[CoreAction]) {
+        let unfit: HashSet<WindowId> = actions
+            .iter()
+            .filter_map(|action| match action {
+                CoreAction::WindowDoesNotFit { id } => Some(*id),
+                _ => None,
+            })
+            .collect();
+        for action in actions {
+            if let CoreAction::SetWindowGeometry { id, .. } = action {
+                if let Some(sent) = self.windows.get_mut(id).filter(|_| !unfit.contains(id)) {
+                    sent.unfit = false;
+                }
+            }
+        }
+    }
+
+    /// Forget what was sent for `id`, e.g. once the window is gone.
+    pub fn forget(&mut self, id: WindowId) {
+        self.windows.remove(&id);
+    }
+
+    /// Forget everything, so the next batch is sent in full.
+    pub fn clear(&mut self) {
+        self.windows.clear();
+        self.headers = None;
+    }
+
+    /// Note `action` as sent, returning whether the backend needs it.
+    fn record(&mut self, action: &CoreAction) -> bool {
+        match action {
+            CoreAction::SetWindowGeometry { id, x, y, w, h } => {
+                let geometry = Some(Geometry::new(*x, *y, *w, *h));
+                let sent = self.windows.entry(*id).or_default();
+                let changed = sent.geometry != geometry;
+                sent.geometry = geometry;
+                changed
+            }
+            CoreAction::SetWindowFrame { id, frame, client } => {
+                let frame = Some((*frame, *client));
+                let sent = self.windows.entry(*id).or_default();
+                let changed = sent.frame != frame;
+                sent.frame = frame;
+                changed
+            }
+            CoreAction::SetWindowBorder { id, borders } => {
+                let sent = self.windows.entry(*id).or_default();
+                let changed = sent.borders != Some(*borders);
+                sent.borders = Some(*borders);
+                changed
+            }
+            CoreAction::WindowDoesNotFit { id } => {
+                !mem::replace(&mut self.windows.entry(*id).or_default().unfit, true)
+            }
+            CoreAction::SetDecorations { headers } => {
+                if self.headers.as_ref() == Some(headers) {
+                    return false;
+                }
+                self.headers = Some(headers.clone());
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+/// What an action sets. Within a batch, a later action with the same key
+/// replaces an earlier one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Key {
+    Geometry(WindowId),
+    Frame(WindowId),
+    Border(WindowId),
+    DoesNotFit(WindowId),
+    Visible(WindowId),
+    Floating(WindowId),
+    Focus,
+    Decorations,
+    Workspace,
+}
+
+const fn key(action: &CoreAction) -> Option<Key> {
+    Some(match *action {
+        CoreAction::SetWindowGeometry { id, .. } => Key::Geometry(id),
+        CoreAction::SetWindowFrame { id, .. } => Key::Frame(id),
+        CoreAction::SetWindowBorder { id, .. } => Key::Border(id),
+        CoreAction::WindowDoesNotFit { id } => Key::DoesNotFit(id),
+        CoreAction::SetVisible { id, .. } => Key::Visible(id),
+        CoreAction::SetFloating { id, .. } => Key::Floating(id),
+        CoreAction::SetFocus { .. } => Key::Focus,
+        CoreAction::SetDecorations { .. } => Key::Decorations,
+        CoreAction::WorkspaceChanged { .. } => Key::Workspace,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(id: u64, x: i32) -> CoreAction {
+        CoreAction::SetWindowGeometry {
+            id: WindowId(id),
+            x,
+            y: 0,
+            w: 100,
+            h: 100,
+        }
+    }
+
+    #[test]
+    fn test_flush_coalesces_and_diffs() {
+        let mut outbox = Outbox::new();
+        let actions = outbox.flush(vec![
+            geometry(1, 0),
+            CoreAction::SetFocus {
+                id: Some(WindowId(1)),
+            },
+            geometry(2, 0),
+            geometry(1, 50),
+            CoreAction::SetFocus {
+                id: Some(WindowId(2)),
+            },
+        ]);
+        assert_eq!(
+            actions,
+            vec![
+                geometry(2, 0),
+                geometry(1, 50),
+                CoreAction::SetFocus {
+                    id: Some(WindowId(2)),
+                },
+            ]
+        );
+
+        // Only what moved is sent again.
+        let actions = outbox.flush(vec![geometry(1, 50), geometry(2, 10)]);
+        assert_eq!(actions, vec![geometry(2, 10)]);
+
+        outbox.forget(WindowId(1));
+        let actions = outbox.flush(vec![geometry(1, 50), geometry(2, 10)]);
+        assert_eq!(actions, vec![geometry(1, 50)]);
+    }
+
+    #[test]
+    fn test_unfit_windows_reported_once() {
+        let mut outbox = Outbox::new();
+        let unfit = CoreAction::WindowDoesNotFit { id: WindowId(1) };
+        let actions = outbox.flush(vec![geometry(1, 0), unfit.clone()]);
+        assert_eq!(actions, vec![geometry(1, 0), unfit.clone()]);
+
+        // Still not fitting: nothing new to say.
+        let actions = outbox.flush(vec![geometry(1, 0), unfit.clone()]);
+        assert_eq!(actions, vec![]);
+
+        // Once it has fit, it is reported again should it stop fitting.
+        let actions = outbox.flush(vec![geometry(1, 10)]);
+        assert_eq!(actions, vec![geometry(1, 10)]);
+        let actions = outbox.flush(vec![geometry(1, 10), unfit.clone()]);
+        assert_eq!(actions, vec![unfit]);
+    }
+}
@@ -0,0 +1,220 @@
+//! Saved layouts (i3 `append_layout`).
+//!
+//! A workspace's container tree can be written out as JSON, with each
+//! window recorded as the criteria that identify it. Appending the layout
+//! later rebuilds the containers and leaves empty placeholders where the
+//! windows were; a placeholder swallows the first mapped window that
+//! matches its criteria.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::layout::{
+    Container, ContainerArena, ContainerId, LayoutMode, LayoutNode, LayoutTree, SplitDirection,
+    Subtree,
+};
+use crate::window::{Window, WindowCriteria, WindowId};
+
+/// One node of a saved layout: a container when it has `nodes`, otherwise
+/// a placeholder for the window matching `swallows`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutSpec {
+    #[serde(default)]
+    pub layout: LayoutMode,
+    #[serde(default)]
+    pub orientation: SplitDirection,
+    /// Share of the parent container, from 0.0 to 1.0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swallows: Option<WindowCriteria>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<Self>,
+}
+
+impl LayoutSpec {
+    /// Describe `tree`, recording each window by its app id and class.
+    #[must_use]
+    pub fn from_tree(tree: &LayoutTree, windows: &HashMap<WindowId, Window>) -> Option<Self> {
+        Self::from_node(tree, windows, LayoutNode::Container(tree.root?), None)
+    }
+
+    fn from_node(
+        tree: &LayoutTree,
+        windows: &HashMap<WindowId, Window>,
+        node: LayoutNode,
+        percent: Option<f64>,
+    ) -> Option<Self> {
+        let container_id = match node {
+            LayoutNode::Window(id) => {
+                return Some(Self {
+                    percent,
+                    swallows: Some(criteria_for(windows.get(&id)?)),
+                    ..Self::default()
+                });
+            }
+            LayoutNode::Container(id) => id,
+        };
+        let container = tree.containers.get(container_id)?;
+        if container.is_placeholder() {
+            return Some(Self {
+                percent,
+                swallows: container.swallows.clone(),
+                ..Self::default()
+            });
+        }
+        Some(Self {
+            layout: container.layout,
+            orientation: container.split_direction,
+            percent,
+            swallows: None,
+            nodes: container
+                .children
+                .iter()
+                .zip(&container.ratios)
+                .filter_map(|(&child, &ratio)| Self::from_node(tree, windows, child, Some(ratio)))
+                .collect(),
+        })
+    }
+
+    /// Parse a layout from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `json` is not a valid layout.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serialize the layout as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Fails if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Read a layout saved with [`LayoutSpec::to_json`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or does not hold a valid layout.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read layout file: {}", path.display()))?;
+        Self::from_json(&content)
+            .with_context(|| format!("Failed to parse layout file: {}", path.display()))
+    }
+
+    /// Build the containers and placeholders of this layout, detached from
+    /// any tree. A lone placeholder is wrapped in a split container.
+    #[must_use]
+    pub fn build(&self, config: &Config) -> Subtree {
+        let mut containers = ContainerArena::default();
+        let root = if self.nodes.is_empty() {
+            let wrapper = Self {
+                nodes: vec![self.clone()],
+                ..Self::default()
+            };
+            wrapper.build_container(config, &mut containers)
+        } else {
+            self.build_container(config, &mut containers)
+        };
+        Subtree {
+            root,
+            containers: containers.into_values().collect(),
+            windows: Vec::new(),
+        }
+    }
+
+    fn build_container(&self, config: &Config, containers: &mut ContainerArena) -> ContainerId {
+        let mut container = Container::new(self.layout, self.orientation);
+        container.gap = config.gaps.inner;
+        container.set_master_ratio(config.general.master_ratio);
+        container.master_count = config.general.master_count;
+
+        let mut percents = Vec::new();
+        for node in &self.nodes {
+            if node.nodes.is_empty() && node.swallows.is_none() {
+                continue;
+            }
+            let child = node.build_container(config, containers);
+            container.add_child(LayoutNode::Container(child));
+            percents.push(node.percent);
+        }
+        // Saved shares replace the even split when every child has one.
+        let total: f64 = percents.iter().flatten().sum();
+        if total > 0.0 && percents.iter().all(Option::is_some) {
+            container.ratios = percents.iter().flatten().map(|p| p / total).collect();
+        }
+
+        if self.nodes.is_empty() {
+            container.swallows.clone_from(&self.swallows);
+        }
+        let id = containers.insert(container);
+        for i in 0..containers[id].children.len() {
+            if let LayoutNode::Container(child) = containers[id].children[i] {
+                if let Some(child) = containers.get_mut(child) {
+                    child.parent = Some(id);
+                }
+            }
+        }
+        id
+    }
+}
+
+/// Criteria that pick out `window` again: its app id and class, falling
+/// back to the title when both are empty.
+fn criteria_for(window: &Window) -> WindowCriteria {
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    let mut criteria = WindowCriteria {
+        app_id: non_empty(&window.app_id),
+        class: non_empty(&window.class),
+        ..WindowCriteria::default()
+    };
+    if criteria.app_id.is_none() && criteria.class.is_none() {
+        criteria.title = non_empty(&window.title);
+    }
+    criteria
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_swallow() {
+        let config = Config::default();
+        let mut tree = LayoutTree::new();
+        let editor = Window::new(WindowId(1), "editor".into(), "Editor".into());
+        let term = Window::new(WindowId(2), "term".into(), "Term".into());
+        tree.add_window(editor.id, &config);
+        tree.add_window(term.id, &config);
+        let root = tree.root.unwrap();
+        tree.containers.get_mut(root).unwrap().ratios = vec![0.7, 0.3];
+
+        let mut windows = HashMap::new();
+        windows.insert(term.id, term.clone());
+        windows.insert(editor.id, editor);
+        let spec = LayoutSpec::from_tree(&tree, &windows).unwrap();
+        let spec = LayoutSpec::from_json(&spec.to_json().unwrap()).unwrap();
+        assert_eq!(spec.nodes.len(), 2);
+        assert_eq!(spec.nodes[0].percent, Some(0.7));
+
+        let mut restored = LayoutTree::new();
+        restored.attach_subtree(spec.build(&config));
+        let placeholder = restored.placeholder_for(&term).unwrap();
+        assert!(restored.swallow(placeholder, term.id));
+        assert!(restored.placeholder_for(&term).is_none());
+
+        let root = &restored.containers[restored.root.unwrap()];
+        assert_eq!(root.children[1], LayoutNode::Window(term.id));
+        assert_eq!(root.ratios, vec![0.7, 0.3]);
+    }
+}
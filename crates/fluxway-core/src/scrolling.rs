@@ -0,0 +1,323 @@
+//! Scrollable column layout (PaperWM/niri style).
+//!
+//! Windows sit in columns on a horizontal strip that may be wider than the
+//! output. The workspace keeps a viewport offset into the strip and scrolls
+//! it just far enough to keep the focused column on screen. Each column
+//! stacks its windows vertically.
+
+use std::collections::HashMap;
+
+use crate::layout::{
+    split_evenly, Container, ContainerArena, ContainerId, Direction, SplitDirection,
+};
+use crate::state::Geometry;
+use crate::window::WindowId;
+
+/// Column widths, as fractions of the output, that `column width cycle`
+/// steps through.
+pub const PRESET_WIDTHS: [f64; 3] = [1.0 / 3.0, 1.0 / 2.0, 2.0 / 3.0];
+
+/// Preset a new column starts with (one half).
+const DEFAULT_PRESET: usize = 1;
+
+/// A column of vertically stacked windows.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub id: ContainerId,
+    pub windows: Vec<WindowId>,
+    pub focused: usize,
+    /// Index into [`PRESET_WIDTHS`].
+    pub width: usize,
+}
+
+impl Column {
+    fn new(id: ContainerId, window_id: WindowId) -> Self {
+        Self {
+            id,
+            windows: vec![window_id],
+            focused: 0,
+            width: DEFAULT_PRESET,
+        }
+    }
+
+    #[must_use]
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.windows.get(self.focused).copied()
+    }
+}
+
+/// The columns of a scrolling workspace.
+#[derive(Debug, Default)]
+pub struct ScrollingLayout {
+    pub columns: Vec<Column>,
+    pub focused_column: usize,
+    pub gap: u32,
+    pub window_geometries: HashMap<WindowId, Geometry>,
+    /// A vertical split container per column, numbering the columns.
+    containers: ContainerArena,
+}
+
+impl ScrollingLayout {
+    /// An empty strip numbering its columns in `containers`, which should
+    /// be an arena no live tree shares IDs with.
+    #[must_use]
+    pub fn new(gap: u32, containers: ContainerArena) -> Self {
+        Self {
+            gap,
+            containers,
+            ..Self::default()
+        }
+    }
+
+    /// Open `window_id` in a new column right of the focused one.
+    pub fn add_window(&mut self, window_id: WindowId) {
+        let index = if self.columns.is_empty() {
+            0
+        } else {
+            self.focused_column + 1
+        };
+        let id = self
+            .containers
+            .insert(Container::new_split(SplitDirection::Vertical));
+        let column = Column::new(id, window_id);
+        self.columns.insert(index, column);
+        self.focused_column = index;
+    }
+
+    pub fn remove_window(&mut self, window_id: WindowId) -> bool {
+        let Some(index) = self.column_of(window_id) else {
+            return false;
+        };
+        let column = &mut self.columns[index];
+        column.windows.retain(|&id| id != window_id);
+        column.focused = column.focused.min(column.windows.len().saturating_sub(1));
+        if column.windows.is_empty() {
+            let column = self.columns.remove(index);
+            self.containers.remove(column.id);
+            if self.focused_column > index || self.focused_column >= self.columns.len() {
+                self.focused_column = self.focused_column.saturating_sub(1);
+            }
+        }
+        self.window_geometries.remove(&window_id);
+        true
+    }
+
+    /// Index of the column holding `window_id`.
+    #[must_use]
+    pub fn column_of(&self, window_id: WindowId) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.windows.contains(&window_id))
+    }
+
+    pub fn focus_window(&mut self, window_id: WindowId) -> bool {
+        let Some(index) = self.column_of(window_id) else {
+            return false;
+        };
+        let column = &mut self.columns[index];
+        if let Some(position) = column.windows.iter().position(|&id| id == window_id) {
+            column.focused = position;
+        }
+        self.focused_column = index;
+        true
+    }
+
+    #[must_use]
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.columns.get(self.focused_column)?.focused_window()
+    }
+
+    /// All windows, column by column from the left.
+    pub fn windows(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.columns
+            .iter()
+            .flat_map(|column| column.windows.iter().copied())
+    }
+
+    /// Move focus to the neighbouring column (left/right) or to the
+    /// neighbouring window inside the focused column (up/down). Focus does
+    /// not wrap around the strip.
+    pub fn focus_direction(&mut self, direction: Direction) -> Option<WindowId> {
+        if direction.is_horizontal() {
+            self.focused_column = step(self.focused_column, self.columns.len(), direction)?;
+        } else {
+            let column = self.columns.get_mut(self.focused_column)?;
+            column.focused = step(column.focused, column.windows.len(), direction)?;
+        }
+        self.focused_window()
+    }
+
+    /// Move the focused column left/right along the strip, or the focused
+    /// window up/down inside its column.
+    pub fn move_direction(&mut self, direction: Direction) -> bool {
+        if direction.is_horizontal() {
+            let Some(target) = step(self.focused_column, self.columns.len(), direction) else {
+                return false;
+            };
+            self.columns.swap(self.focused_column, target);
+            self.focused_column = target;
+        } else {
+            let Some(column) = self.columns.get_mut(self.focused_column) else {
+                return false;
+            };
+            let Some(target) = step(column.focused, column.windows.len(), direction) else {
+                return false;
+            };
+            column.windows.swap(column.focused, target);
+            column.focused = target;
+        }
+        true
+    }
+
+    /// Step the focused column to the next preset width.
+    pub fn cycle_column_width(&mut self) -> bool {
+        let Some(column) = self.columns.get_mut(self.focused_column) else {
+            return false;
+        };
+        column.width = (column.width + 1) % PRESET_WIDTHS.len();
+        true
+    }
+
+    /// Pull the first window of the column to the right into the bottom of
+    /// the focused column.
+    pub fn consume_into_column(&mut self) -> bool {
+        let next = self.focused_column + 1;
+        let Some(window_id) = self
+            .columns
+            .get(next)
+            .and_then(|c| c.windows.first().copied())
+        else {
+            return false;
+        };
+        self.remove_window(window_id);
+        let column = &mut self.columns[self.focused_column];
+        column.windows.push(window_id);
+        true
+    }
+
+    /// Move the focused window out of a shared column into a new column
+    /// on its right.
+    pub fn expel_from_column(&mut self) -> bool {
+        let Some(window_id) = self.focused_window() else {
+            return false;
+        };
+        if self.columns[self.focused_column].windows.len() < 2 {
+            return false;
+        }
+        self.remove_window(window_id);
+        self.add_window(window_id);
+        true
+    }
+
+    /// Place the columns side by side on the strip and scroll `viewport` so
+    /// the focused column is fully visible within `area`.
+    ///
+    /// Geometries are in output coordinates: windows scrolled off to the
+    /// left get negative offsets from `area`, those off to the right lie
+    /// past its right edge.
+    pub fn calculate_layout(&mut self, area: Geometry, viewport: &mut i32) {
+        self.window_geometries.clear();
+
+        let widths: Vec<u32> = self
+            .columns
+            .iter()
+            .map(|column| (f64::from(area.width) * PRESET_WIDTHS[column.width]) as u32)
+            .collect();
+        let mut starts = Vec::with_capacity(widths.len());
+        let mut x = 0i32;
+        for &width in &widths {
+            starts.push(x);
+            x += width as i32 + self.gap as i32;
+        }
+
+        if let (Some(&start), Some(&width)) = (
+            starts.get(self.focused_column),
+            widths.get(self.focused_column),
+        ) {
+            let end = start + width as i32;
+            if start < *viewport {
+                *viewport = start;
+            } else if end > *viewport + area.width as i32 {
+                *viewport = end - area.width as i32;
+            }
+        }
+
+        for (i, column) in self.columns.iter().enumerate() {
+            let strip = Geometry::new(
+                area.x + starts[i] - *viewport,
+                area.y,
+                widths[i],
+                area.height,
+            );
+            let n = column.windows.len() as u32;
+            let slices = split_evenly(strip, n, self.gap, SplitDirection::Vertical);
+            for (&window_id, slice) in column.windows.iter().zip(slices) {
+                self.window_geometries.insert(window_id, slice);
+            }
+        }
+    }
+}
+
+/// Neighbouring index of `index` in `direction`, without wrapping.
+fn step(index: usize, len: usize, direction: Direction) -> Option<usize> {
+    match direction {
+        Direction::Left | Direction::Up => index.checked_sub(1),
+        Direction::Right | Direction::Down => Some(index + 1).filter(|&next| next < len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport_follows_focused_column() {
+        let mut layout = ScrollingLayout::new(0, ContainerArena::default());
+        for id in 1..=4 {
+            layout.add_window(WindowId(id));
+        }
+        let area = Geometry::new(0, 0, 1200, 800);
+        let mut viewport = 0;
+
+        // Four half-width columns: focusing the last one scrolls right.
+        layout.calculate_layout(area, &mut viewport);
+        assert_eq!(viewport, 1200);
+        assert_eq!(
+            layout.window_geometries[&WindowId(4)],
+            Geometry::new(600, 0, 600, 800)
+        );
+        assert_eq!(layout.window_geometries[&WindowId(1)].x, -1200);
+
+        assert_eq!(layout.focus_direction(Direction::Left), Some(WindowId(3)));
+        assert_eq!(layout.focus_direction(Direction::Left), Some(WindowId(2)));
+        layout.calculate_layout(area, &mut viewport);
+        assert_eq!(viewport, 600);
+        assert_eq!(layout.window_geometries[&WindowId(2)].x, 0);
+
+        assert!(layout.cycle_column_width());
+        assert_eq!(layout.columns[1].width, 2);
+    }
+
+    #[test]
+    fn test_consume_and_expel() {
+        let mut layout = ScrollingLayout::new(0, ContainerArena::default());
+        layout.add_window(WindowId(1));
+        layout.add_window(WindowId(2));
+        layout.focus_window(WindowId(1));
+
+        assert!(layout.consume_into_column());
+        assert_eq!(layout.columns.len(), 1);
+        assert_eq!(layout.columns[0].windows, vec![WindowId(1), WindowId(2)]);
+
+        let mut viewport = 0;
+        layout.calculate_layout(Geometry::new(0, 0, 1200, 800), &mut viewport);
+        assert_eq!(
+            layout.window_geometries[&WindowId(2)],
+            Geometry::new(0, 400, 600, 400)
+        );
+
+        assert!(layout.expel_from_column());
+        assert_eq!(layout.columns.len(), 2);
+        assert_eq!(layout.focused_window(), Some(WindowId(1)));
+    }
+}
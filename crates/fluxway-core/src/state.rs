@@ -5,9 +5,9 @@ use std::collections::HashMap;
 use indexmap::IndexMap;
 
 use crate::config::Config;
-use crate::layout::{Container, ContainerId};
+use crate::layout::{Container, ContainerId, LayoutNode};
 use crate::window::{Window, WindowId, WindowState};
-use crate::workspace::{Workspace, WorkspaceId};
+use crate::workspace::{parse_number, Workspace, WorkspaceId};
 
 /// Geometry of a rectangular region.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -36,6 +36,12 @@ impl Geometry {
             && y < self.y + self.height as i32
     }
 
+    #[allow(clippy::cast_possible_wrap)]
+    #[must_use]
+    pub const fn center(self) -> (i32, i32) {
+        (self.x + self.width as i32 / 2, self.y + self.height as i32 / 2)
+    }
+
     #[allow(clippy::cast_possible_wrap)]
     pub const fn intersects(self, other: Self) -> bool {
         self.x < other.x + other.width as i32
@@ -71,7 +77,9 @@ pub struct Output {
     pub geometry: Geometry,
     pub scale: f64,
     pub refresh_rate: u32,
+    /// Workspaces bound to this output, in order.
     pub workspaces: Vec<WorkspaceId>,
+    /// The workspace this output shows.
     pub active_workspace: Option<WorkspaceId>,
 }
 
@@ -79,13 +87,21 @@ pub struct Output {
 #[derive(Debug, Clone, Default)]
 pub struct FocusState {
     pub focused_window: Option<WindowId>,
+    /// Container selected with `focus parent`. `focused_window` keeps
+    /// input focus inside it.
+    pub focused_container: Option<ContainerId>,
     pub previous_window: Option<WindowId>,
     pub focused_workspace: Option<WorkspaceId>,
+    /// Name of the workspace focused before the current one, for
+    /// `back_and_forth`. A name, since that workspace is destroyed if it
+    /// was left empty.
+    pub previous_workspace: Option<String>,
     pub focus_history: Vec<WindowId>,
 }
 
 impl FocusState {
     pub fn set_focused(&mut self, window_id: WindowId) {
+        self.focused_container = None;
         if self.focused_window != Some(window_id) {
             self.previous_window = self.focused_window;
             if let Some(prev) = self.focused_window {
@@ -102,6 +118,15 @@ impl FocusState {
     pub fn clear_focused(&mut self) {
         self.previous_window = self.focused_window;
         self.focused_window = None;
+        self.focused_container = None;
+    }
+
+    /// The selected node: the focused container if one is selected,
+    /// otherwise the focused window.
+    pub fn selection(&self) -> Option<LayoutNode> {
+        self.focused_container
+            .map(LayoutNode::Container)
+            .or_else(|| self.focused_window.map(LayoutNode::Window))
     }
 }
 
@@ -141,8 +166,12 @@ pub struct State {
     pub focus: FocusState,
     pub scratchpad: Vec<WindowId>,
     pub marks: HashMap<String, WindowId>,
+    pub container_marks: HashMap<String, ContainerId>,
     pub running: bool,
     pub layout_dirty: bool,
+    /// Last workspace ID handed out. IDs are not reused, so a destroyed
+    /// workspace's ID does not find its successor.
+    last_workspace_id: u32,
     pub pointer_position: (f64, f64),
     pub grabbed_window: Option<GrabbedWindow>,
 }
@@ -158,25 +187,259 @@ impl State {
             focus: FocusState::default(),
             scratchpad: Vec::new(),
             marks: HashMap::new(),
+            container_marks: HashMap::new(),
             running: true,
             layout_dirty: false,
+            last_workspace_id: 0,
             pointer_position: (0.0, 0.0),
             grabbed_window: None,
         };
 
-        // Create default workspaces
-        for i in 1..=10 {
-            let ws_id = WorkspaceId(i);
-            let workspace = Workspace::new(ws_id, format!("{i}"));
-            state.workspaces.insert(ws_id, workspace);
+        // The workspaces named in the config, or else just "1". Further
+        // workspaces are created on demand.
+        if state.config.workspaces.is_empty() {
+            state.create_workspace("1".to_string(), None);
         }
+        state.create_configured_workspaces();
+        state.focus.focused_workspace = state.workspaces.keys().next().copied();
 
         state
     }
 
+    /// Create any workspace named in the config that does not exist yet.
+    pub fn create_configured_workspaces(&mut self) {
+        let names: Vec<String> = self
+            .config
+            .workspaces
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect();
+        for name in names {
+            self.workspace_named(&name);
+        }
+    }
+
+    /// Add a workspace called `name`, in order, on `output` or else the
+    /// output it is assigned to.
+    fn create_workspace(&mut self, name: String, output: Option<String>) -> WorkspaceId {
+        self.last_workspace_id += 1;
+        let ws_id = WorkspaceId(self.last_workspace_id);
+        let mut workspace = Workspace::new(ws_id, name);
+        workspace.output = output;
+        self.workspaces.insert(ws_id, workspace);
+        self.workspaces
+            .sort_by(|_, a, _, b| a.sort_key().cmp(&b.sort_key()));
+        self.assign_workspace_outputs();
+        ws_id
+    }
+
+    /// Bind each workspace to its preferred output, taking on that
+    /// output's geometry.
+    pub fn assign_workspace_outputs(&mut self) {
+        let assignments: Vec<_> = self
+            .workspaces
+            .values()
+            .map(|ws| {
+                self.preferred_output(ws)
+                    .map(|output| (output.name.clone(), output.geometry))
+            })
+            .collect();
+        for (ws, assignment) in self.workspaces.values_mut().zip(assignments) {
+            let Some((name, geometry)) = assignment else {
+                ws.output = None;
+                continue;
+            };
+            ws.output = Some(name);
+            if ws.geometry != geometry {
+                ws.set_geometry(geometry);
+                self.layout_dirty = true;
+            }
+        }
+        self.update_output_workspaces();
+    }
+
+    /// The output named in the workspace's config entry, while that is
+    /// connected, or else the one it is on, or else the focused output.
+    fn preferred_output(&self, workspace: &Workspace) -> Option<&Output> {
+        let connected = |name: &str| self.outputs.values().find(|output| output.name == name);
+        self.config
+            .workspace_config(&workspace.name)
+            .and_then(|entry| entry.output.as_deref())
+            .and_then(connected)
+            .or_else(|| workspace.output.as_deref().and_then(connected))
+            .or_else(|| self.focused_output())
+            .or_else(|| self.outputs.values().next())
+    }
+
+    /// List each output's workspaces and make sure it shows one of them,
+    /// the focused workspace if that is among them.
+    fn update_output_workspaces(&mut self) {
+        let focused = self.focus.focused_workspace;
+        for output in self.outputs.values_mut() {
+            output.workspaces = self
+                .workspaces
+                .values()
+                .filter(|ws| ws.output.as_deref() == Some(output.name.as_str()))
+                .map(|ws| ws.id)
+                .collect();
+            let contains = |id: &WorkspaceId| output.workspaces.contains(id);
+            output.active_workspace = focused
+                .filter(contains)
+                .or_else(|| output.active_workspace.filter(contains))
+                .or_else(|| output.workspaces.first().copied());
+        }
+    }
+
+    /// Give `output_id` a workspace if it has none, numbered with the
+    /// lowest free number.
+    #[cfg(feature = "multi-output")]
+    pub fn populate_output(&mut self, output_id: u64) {
+        let Some(output) = self.outputs.get(&output_id) else {
+            return;
+        };
+        if !output.workspaces.is_empty() {
+            return;
+        }
+        let output_name = output.name.clone();
+        let number = (1..=self.workspaces.len() as u32 + 1)
+            .find(|&n| self.workspaces.values().all(|ws| ws.number != Some(n)))
+            .unwrap_or_default();
+        self.create_workspace(number.to_string(), Some(output_name));
+    }
+
+    /// The output `workspace_id` is bound to.
+    #[must_use]
+    pub fn workspace_output(&self, workspace_id: WorkspaceId) -> Option<&Output> {
+        let name = self.workspaces.get(&workspace_id)?.output.as_deref()?;
+        self.outputs.values().find(|output| output.name == name)
+    }
+
+    /// The output showing the focused workspace.
+    #[must_use]
+    pub fn focused_output(&self) -> Option<&Output> {
+        self.workspace_output(self.focus.focused_workspace?)
+    }
+
+    /// The workspace called `name`, created if there is none.
+    pub fn workspace_named(&mut self, name: &str) -> WorkspaceId {
+        if let Some(ws) = self.workspaces.values().find(|ws| ws.name == name) {
+            return ws.id;
+        }
+        self.create_workspace(name.to_string(), None)
+    }
+
+    /// The first workspace numbered `number`, or a new one named after
+    /// the number.
+    pub fn workspace_numbered(&mut self, number: u32) -> WorkspaceId {
+        if let Some(ws) = self.workspaces.values().find(|ws| ws.number == Some(number)) {
+            return ws.id;
+        }
+        self.create_workspace(number.to_string(), None)
+    }
+
+    /// Call `workspace_id` `name`, renumbering it and moving it into
+    /// order. Returns the old name, or `None` if there is no such
+    /// workspace or another one is already called `name`.
+    pub fn rename_workspace(&mut self, workspace_id: WorkspaceId, name: &str) -> Option<String> {
+        if self
+            .workspaces
+            .values()
+            .any(|ws| ws.name == name && ws.id != workspace_id)
+        {
+            return None;
+        }
+        let workspace = self.workspaces.get_mut(&workspace_id)?;
+        let old_name = std::mem::replace(&mut workspace.name, name.to_string());
+        workspace.number = parse_number(name);
+        self.workspaces
+            .sort_by(|_, a, _, b| a.sort_key().cmp(&b.sort_key()));
+        if self.focus.previous_workspace.as_ref() == Some(&old_name) {
+            self.focus.previous_workspace = Some(name.to_string());
+        }
+        self.update_output_workspaces();
+        self.layout_dirty = true;
+        Some(old_name)
+    }
+
+    /// Whether `workspace_id` is shown on screen.
+    #[must_use]
+    pub fn is_workspace_visible(&self, workspace_id: WorkspaceId) -> bool {
+        self.focus.focused_workspace == Some(workspace_id)
+            || self
+                .outputs
+                .values()
+                .any(|output| output.active_workspace == Some(workspace_id))
+    }
+
+    /// The workspaces shown on screen: each output's active workspace,
+    /// and the focused one.
+    #[must_use]
+    pub fn visible_workspaces(&self) -> Vec<WorkspaceId> {
+        let mut visible: Vec<_> = self
+            .outputs
+            .values()
+            .filter_map(|output| output.active_workspace)
+            .collect();
+        if let Some(focused) = self.focus.focused_workspace {
+            if !visible.contains(&focused) {
+                visible.push(focused);
+            }
+        }
+        visible
+    }
+
+    /// Destroy `workspace_id` if it is empty and not shown. Workspaces
+    /// named in the config are kept.
+    fn destroy_workspace_if_unused(&mut self, workspace_id: WorkspaceId) {
+        if !self.is_workspace_visible(workspace_id)
+            && self.workspaces.get(&workspace_id).is_some_and(|ws| {
+                ws.is_disposable() && self.config.workspace_config(&ws.name).is_none()
+            })
+        {
+            self.workspaces.shift_remove(&workspace_id);
+            self.update_output_workspaces();
+        }
+    }
+
+    /// Focus `workspace_id` and show it on its output, remembering the
+    /// previously focused workspace. The previously focused workspace and
+    /// the one the output showed are destroyed if left empty.
+    fn set_focused_workspace(&mut self, workspace_id: WorkspaceId) {
+        let name = self.workspaces.get(&workspace_id).and_then(|ws| ws.output.clone());
+        let replaced = self
+            .outputs
+            .values_mut()
+            .find(|output| name.as_ref() == Some(&output.name))
+            .and_then(|output| output.active_workspace.replace(workspace_id))
+            .filter(|&id| id != workspace_id);
+
+        let previous = self.focus.focused_workspace.replace(workspace_id);
+        if let Some(previous) = previous.filter(|&id| id != workspace_id) {
+            self.focus.previous_workspace =
+                self.workspaces.get(&previous).map(|ws| ws.name.clone());
+            self.destroy_workspace_if_unused(previous);
+        }
+        if let Some(replaced) = replaced {
+            self.destroy_workspace_if_unused(replaced);
+        }
+    }
+
     pub fn add_window(&mut self, mut window: Window) -> WindowId {
         let id = window.id;
 
+        // A placeholder from `append_layout` takes the window wherever it is.
+        if !window.state.contains(WindowState::FLOATING) {
+            if let Some((workspace_id, placeholder)) = self.find_placeholder(&window) {
+                window.workspace = Some(workspace_id);
+                if let Some(workspace) = self.workspaces.get_mut(&workspace_id) {
+                    workspace.swallow_window(id, placeholder);
+                }
+                self.windows.insert(id, window);
+                self.layout_dirty = true;
+                return id;
+            }
+        }
+
         let workspace_id = self
             .focus
             .focused_workspace
@@ -186,7 +449,8 @@ impl State {
         window.workspace = Some(workspace_id);
 
         if let Some(workspace) = self.workspaces.get_mut(&workspace_id) {
-            workspace.add_window(id, &self.config);
+            let placement = self.config.placement_for(&window);
+            workspace.place_window(id, placement, &self.config);
         }
 
         self.windows.insert(id, window);
@@ -195,20 +459,41 @@ impl State {
         id
     }
 
+    /// The first placeholder waiting for `window`, searching workspaces in order.
+    #[must_use]
+    pub fn find_placeholder(&self, window: &Window) -> Option<(WorkspaceId, ContainerId)> {
+        self.workspaces
+            .values()
+            .find_map(|ws| Some((ws.id, ws.placeholder_for(window)?)))
+    }
+
     pub fn remove_window(&mut self, window_id: WindowId) -> Option<Window> {
         let window = self.windows.remove(&window_id)?;
 
         if let Some(workspace_id) = window.workspace {
             if let Some(workspace) = self.workspaces.get_mut(&workspace_id) {
                 workspace.remove_window(window_id);
+                if self
+                    .focus
+                    .focused_container
+                    .is_some_and(|id| !workspace.layout.containers.contains_key(id))
+                {
+                    self.focus.focused_container = None;
+                }
             }
+            self.destroy_workspace_if_unused(workspace_id);
         }
 
         if self.focus.focused_window == Some(window_id) {
             self.focus.clear_focused();
             while let Some(next) = self.focus.focus_history.pop() {
-                if self.windows.contains_key(&next) {
+                if let Some(next_window) = self.windows.get(&next) {
                     self.focus.focused_window = Some(next);
+                    if let Some(workspace) =
+                        next_window.workspace.and_then(|id| self.workspaces.get_mut(&id))
+                    {
+                        workspace.focus_tiled(next);
+                    }
                     break;
                 }
             }
@@ -233,10 +518,14 @@ impl State {
         }
 
         self.focus.set_focused(window_id);
-        if let Some(window) = self.windows.get_mut(&window_id) {
-            window.state.insert(WindowState::FOCUSED);
-            if let Some(ws_id) = window.workspace {
-                self.focus.focused_workspace = Some(ws_id);
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        window.state.insert(WindowState::FOCUSED);
+        if let Some(ws_id) = window.workspace {
+            self.set_focused_workspace(ws_id);
+            if let Some(workspace) = self.workspaces.get_mut(&ws_id) {
+                workspace.focus_window(window_id);
             }
         }
     }
@@ -249,11 +538,39 @@ impl State {
         self.focus.focused_workspace.and_then(|id| self.workspaces.get(&id))
     }
 
+    /// Windows covered by the current selection: the focused window, or
+    /// every window below the focused container.
+    #[must_use]
+    pub fn selected_windows(&self) -> Vec<WindowId> {
+        match self.focus.selection() {
+            Some(LayoutNode::Container(id)) => self
+                .container_workspace(id)
+                .map(|ws| ws.layout.windows_in(LayoutNode::Container(id)))
+                .unwrap_or_default(),
+            Some(LayoutNode::Window(id)) => vec![id],
+            None => Vec::new(),
+        }
+    }
+
+    /// The workspace whose layout tree holds `container_id`.
+    #[must_use]
+    pub fn container_workspace(&self, container_id: ContainerId) -> Option<&Workspace> {
+        self.workspaces
+            .values()
+            .find(|ws| ws.layout.containers.contains_key(container_id))
+    }
+
+    /// The workspace a window is assigned to.
+    pub fn window_workspace_mut(&mut self, window_id: WindowId) -> Option<&mut Workspace> {
+        let ws_id = self.windows.get(&window_id)?.workspace?;
+        self.workspaces.get_mut(&ws_id)
+    }
+
     pub fn switch_workspace(&mut self, workspace_id: WorkspaceId) {
         if !self.workspaces.contains_key(&workspace_id) {
             return;
         }
-        self.focus.focused_workspace = Some(workspace_id);
+        self.set_focused_workspace(workspace_id);
 
         if let Some(workspace) = self.workspaces.get(&workspace_id) {
             if let Some(&window_id) = workspace.focus_stack.last() {
@@ -271,6 +588,7 @@ impl State {
 
         let old_workspace = window.workspace;
         window.workspace = Some(target_workspace);
+        let placement = self.config.placement_for(window);
 
         if let Some(old_ws_id) = old_workspace {
             if let Some(old_ws) = self.workspaces.get_mut(&old_ws_id) {
@@ -279,12 +597,56 @@ impl State {
         }
 
         if let Some(new_ws) = self.workspaces.get_mut(&target_workspace) {
-            new_ws.add_window(window_id, &self.config);
+            new_ws.place_window(window_id, placement, &self.config);
+        }
+        if let Some(old_ws_id) = old_workspace {
+            self.destroy_workspace_if_unused(old_ws_id);
         }
 
         self.layout_dirty = true;
     }
 
+    /// Move a tiled container, with everything below it, to another workspace.
+    pub fn move_container_to_workspace(
+        &mut self,
+        container_id: ContainerId,
+        target_workspace: WorkspaceId,
+    ) {
+        if !self.workspaces.contains_key(&target_workspace) {
+            return;
+        }
+        let Some(source) = self.workspaces.values_mut().find(|ws| {
+            ws.id != target_workspace && ws.layout.containers.contains_key(container_id)
+        }) else {
+            return;
+        };
+        let source_id = source.id;
+        let Some(subtree) = source.take_container(container_id) else {
+            return;
+        };
+        self.destroy_workspace_if_unused(source_id);
+
+        for window_id in &subtree.windows {
+            if let Some(window) = self.windows.get_mut(window_id) {
+                window.workspace = Some(target_workspace);
+            }
+        }
+        let Some(target) = self.workspaces.get_mut(&target_workspace) else {
+            return;
+        };
+        // The containers are numbered anew in the target's tree.
+        let ids = target.add_container(subtree);
+        let renumber = |id: &mut ContainerId| {
+            if let Some(&new) = ids.get(id) {
+                *id = new;
+            }
+        };
+        self.focus.focused_container.as_mut().map(renumber);
+        self.container_marks.values_mut().for_each(renumber);
+
+        self.layout_dirty = true;
+    }
+
     pub fn toggle_scratchpad(&mut self, window_id: WindowId) {
         if let Some(pos) = self.scratchpad.iter().position(|&id| id == window_id) {
             self.scratchpad.remove(pos);
@@ -301,25 +663,46 @@ impl State {
     }
 
     pub fn set_mark(&mut self, mark: String, window_id: WindowId) {
+        self.container_marks.remove(&mark);
         self.marks.insert(mark, window_id);
     }
 
+    pub fn set_container_mark(&mut self, mark: String, container_id: ContainerId) {
+        self.marks.remove(&mark);
+        self.container_marks.insert(mark, container_id);
+    }
+
     pub fn goto_mark(&mut self, mark: &str) {
-        if let Some(&window_id) = self.marks.get(mark) {
-            self.focus_window(window_id);
-            if let Some(window) = self.windows.get(&window_id) {
-                if let Some(ws_id) = window.workspace {
-                    if self.focus.focused_workspace != Some(ws_id) {
-                        self.switch_workspace(ws_id);
-                    }
+        let (window_id, container) = if let Some(&window_id) = self.marks.get(mark) {
+            (window_id, None)
+        } else if let Some(&container_id) = self.container_marks.get(mark) {
+            let Some(window_id) = self
+                .container_workspace(container_id)
+                .and_then(|ws| ws.layout.descend_focused(LayoutNode::Container(container_id)))
+            else {
+                // The marked container has been closed since.
+                self.container_marks.remove(mark);
+                return;
+            };
+            (window_id, Some(container_id))
+        } else {
+            return;
+        };
+
+        self.focus_window(window_id);
+        if let Some(window) = self.windows.get(&window_id) {
+            if let Some(ws_id) = window.workspace {
+                if self.focus.focused_workspace != Some(ws_id) {
+                    self.switch_workspace(ws_id);
                 }
             }
         }
+        self.focus.focused_container = container;
     }
 
     pub fn window_at(&self, x: f64, y: f64) -> Option<WindowId> {
         for (_, workspace) in self.workspaces.iter().rev() {
-            if Some(workspace.id) != self.focus.focused_workspace {
+            if !self.is_workspace_visible(workspace.id) {
                 continue;
             }
             for &window_id in workspace.floating_windows.iter().rev() {
@@ -5,6 +5,7 @@
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
+use crate::config::FontConfig;
 use crate::state::Geometry;
 use crate::workspace::WorkspaceId;
 
@@ -73,8 +74,43 @@ impl Default for BorderStyle {
     }
 }
 
+/// Border width on each edge of a window, after `smart_borders` and
+/// `hide_edge_borders` are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Borders {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl Borders {
+    #[must_use]
+    pub const fn uniform(width: u32) -> Self {
+        Self {
+            top: width,
+            right: width,
+            bottom: width,
+            left: width,
+        }
+    }
+
+    /// The client area of `frame`: inside these borders and below a title
+    /// bar `title_bar` high.
+    #[must_use]
+    pub const fn client_area(&self, frame: Geometry, title_bar: u32) -> Geometry {
+        let top = self.top + title_bar;
+        Geometry::new(
+            frame.x + self.left as i32,
+            frame.y + top as i32,
+            frame.width.saturating_sub(self.left + self.right),
+            frame.height.saturating_sub(top + self.bottom),
+        )
+    }
+}
+
 /// Window size constraints.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct SizeHints {
     pub min_width: Option<u32>,
     pub min_height: Option<u32>,
@@ -226,6 +262,17 @@ impl Window {
         self.geometry = Geometry::new(geometry.x, geometry.y, w, h);
     }
 
+    /// Height of the title bar drawn above the client, which only
+    /// `BorderStyle::Normal` has.
+    #[must_use]
+    pub fn title_bar_height(&self, font: &FontConfig) -> u32 {
+        if self.border == BorderStyle::Normal && !self.state.contains(WindowState::FULLSCREEN) {
+            font.title_bar_height()
+        } else {
+            0
+        }
+    }
+
     /// Effective border width.
     pub fn border_width(&self) -> u32 {
         if self.state.contains(WindowState::FULLSCREEN) {
@@ -240,7 +287,7 @@ impl Window {
 }
 
 /// Window matching criteria (for rules).
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WindowCriteria {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub app_id: Option<String>,
@@ -355,6 +402,30 @@ mod tests {
         assert_eq!(hints.constrain(200, 300), (200, 300));
     }
 
+    #[test]
+    fn test_client_area() {
+        let font = FontConfig::default();
+        let mut window = Window::new(WindowId(1), "test".into(), "Test Window".into());
+        let frame = Geometry::new(100, 100, 400, 300);
+        assert_eq!(window.title_bar_height(&font), 0);
+        assert_eq!(
+            Borders::uniform(2).client_area(frame, 0),
+            Geometry::new(102, 102, 396, 296)
+        );
+
+        window.border = BorderStyle::Normal;
+        let title_bar = window.title_bar_height(&font);
+        assert_eq!(title_bar, 23);
+        let borders = Borders {
+            left: 0,
+            ..Borders::uniform(2)
+        };
+        assert_eq!(
+            borders.client_area(frame, title_bar),
+            Geometry::new(100, 125, 398, 273)
+        );
+    }
+
     #[test]
     fn test_window_criteria() {
         let window = Window::new(WindowId(1), "firefox".into(), "Mozilla Firefox".into());
@@ -1,11 +1,16 @@
 //! Workspace management — virtual desktops.
 
+use std::collections::HashMap;
+use std::mem;
+
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
-use crate::layout::{LayoutTree, SplitDirection};
+use crate::config::{Config, GapConfig, HideEdgeBorders, NewWindowPlacement, SmartBorders};
+use crate::decoration::{self, Header};
+use crate::layout::{ContainerId, LayoutMode, LayoutTree, SplitDirection, Subtree};
+use crate::scrolling::ScrollingLayout;
 use crate::state::Geometry;
-use crate::window::WindowId;
+use crate::window::{Borders, Window, WindowId};
 
 /// Unique identifier for workspaces.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -25,6 +30,13 @@ pub struct Workspace {
     pub number: Option<u32>,
     pub output: Option<String>,
     pub layout: LayoutTree,
+    /// Column layout replacing `layout` while the workspace scrolls.
+    pub scrolling: Option<ScrollingLayout>,
+    /// The container tree, set aside while the workspace scrolls and
+    /// restored when it stops.
+    pub suspended_layout: Option<LayoutTree>,
+    /// Horizontal scroll position of the viewport into the column strip.
+    pub viewport_offset: i32,
     pub tiled_windows: Vec<WindowId>,
     pub floating_windows: Vec<WindowId>,
     pub fullscreen_window: Option<WindowId>,
@@ -33,17 +45,32 @@ pub struct Workspace {
     pub urgent: bool,
     pub geometry: Geometry,
     pub work_area: Geometry,
+    /// Gaps set at runtime with `gaps ... current`.
+    pub gaps: Option<GapConfig>,
+}
+
+/// The number of a workspace called `name`: its leading digits, as in
+/// i3, so "3:mail" is workspace 3. Names without any have no number.
+#[must_use]
+pub fn parse_number(name: &str) -> Option<u32> {
+    let end = name
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(name.len());
+    name[..end].parse().ok()
 }
 
 impl Workspace {
     pub fn new(id: WorkspaceId, name: String) -> Self {
-        let number = name.parse::<u32>().ok();
+        let number = parse_number(&name);
         Self {
             id,
             name,
             number,
             output: None,
-            layout: LayoutTree::new(),
+            layout: LayoutTree::with_id(id.0),
+            scrolling: None,
+            suspended_layout: None,
+            viewport_offset: 0,
             tiled_windows: Vec::new(),
             floating_windows: Vec::new(),
             fullscreen_window: None,
@@ -52,15 +79,136 @@ impl Workspace {
             urgent: false,
             geometry: Geometry::default(),
             work_area: Geometry::default(),
+            gaps: None,
         }
     }
 
     pub fn add_window(&mut self, window_id: WindowId, config: &Config) {
+        self.place_window(window_id, config.general.new_window_placement, config);
+    }
+
+    /// Add `window_id` at `placement` in the tree. Scrolling workspaces
+    /// always open a new column beside the focused one.
+    pub fn place_window(
+        &mut self,
+        window_id: WindowId,
+        placement: NewWindowPlacement,
+        config: &Config,
+    ) {
         self.tiled_windows.push(window_id);
-        self.layout.add_window(window_id, config);
+        self.insert_tiled(window_id, placement, config);
         self.focus_stack.push(window_id);
     }
 
+    fn insert_tiled(
+        &mut self,
+        window_id: WindowId,
+        placement: NewWindowPlacement,
+        config: &Config,
+    ) {
+        if let Some(scrolling) = &mut self.scrolling {
+            scrolling.add_window(window_id);
+        } else {
+            self.prepare_new_root(config);
+            self.layout.place_window(window_id, placement, config);
+        }
+    }
+
+    fn remove_tiled(&mut self, window_id: WindowId) {
+        if let Some(scrolling) = &mut self.scrolling {
+            scrolling.remove_window(window_id);
+            if let Some(layout) = &mut self.suspended_layout {
+                layout.remove_window(window_id);
+            }
+        } else {
+            self.layout.remove_window(window_id);
+        }
+    }
+
+    /// Switch between the scrolling column layout and the container tree.
+    ///
+    /// Each tiled window becomes its own column when scrolling starts. The
+    /// tree is set aside meanwhile, with its splits, ratios and containers,
+    /// and comes back when scrolling stops; windows opened in the meantime
+    /// join it after the focused window.
+    pub fn set_scrolling(&mut self, enabled: bool, config: &Config) {
+        if enabled == self.scrolling.is_some() {
+            return;
+        }
+        let focused = self.focused_tiled_window();
+        self.viewport_offset = 0;
+        if enabled {
+            // Columns are numbered like the empty tree standing in for the
+            // set-aside one, which gets no containers while scrolling.
+            let columns = self.layout.containers.successor();
+            let successor = self.layout.successor();
+            self.suspended_layout = Some(mem::replace(&mut self.layout, successor));
+            self.scrolling = Some(ScrollingLayout::new(config.gaps.inner, columns));
+            for &window_id in &self.tiled_windows.clone() {
+                self.insert_tiled(window_id, NewWindowPlacement::AfterFocused, config);
+            }
+        } else {
+            self.scrolling = None;
+            if let Some(layout) = self.suspended_layout.take() {
+                self.layout = layout;
+            }
+            for &window_id in &self.tiled_windows.clone() {
+                if self.layout.container_of(window_id).is_none() {
+                    self.insert_tiled(window_id, NewWindowPlacement::AfterFocused, config);
+                }
+            }
+        }
+        if let Some(focused) = focused {
+            self.focus_tiled(focused);
+        }
+    }
+
+    #[must_use]
+    pub const fn is_scrolling(&self) -> bool {
+        self.scrolling.is_some()
+    }
+
+    /// Whether a tiled window's geometry overlaps the visible work area.
+    /// Always true outside the scrolling layout.
+    #[must_use]
+    pub fn window_in_viewport(&self, window_id: WindowId) -> bool {
+        self.scrolling.is_none()
+            || self
+                .window_geometry(window_id)
+                .map_or(true, |geometry| geometry.intersects(self.work_area))
+    }
+
+    fn focused_tiled_window(&self) -> Option<WindowId> {
+        self.scrolling.as_ref().map_or_else(
+            || self.layout.focused_window(),
+            ScrollingLayout::focused_window,
+        )
+    }
+
+    /// Point the tiling layout's focus at `window_id` without touching the
+    /// focus stack.
+    pub fn focus_tiled(&mut self, window_id: WindowId) {
+        if let Some(scrolling) = &mut self.scrolling {
+            scrolling.focus_window(window_id);
+        } else {
+            self.layout.focus_window(window_id);
+        }
+    }
+
+    /// Pick the root split direction from the workspace's shape, and its
+    /// layout, before the first tiled window creates the root container.
+    /// The workspace's config entry overrides the general settings.
+    fn prepare_new_root(&mut self, config: &Config) {
+        if self.layout.root.is_none() {
+            let entry = config.workspace_config(&self.name);
+            self.layout.default_direction = entry
+                .and_then(|entry| entry.orientation)
+                .unwrap_or(config.general.default_orientation)
+                .split_direction(self.work_area.width, self.work_area.height);
+            self.layout.default_layout = entry.and_then(|entry| entry.layout);
+        }
+    }
+
     pub fn add_floating_window(&mut self, window_id: WindowId) {
         self.floating_windows.push(window_id);
         self.focus_stack.push(window_id);
@@ -68,7 +216,7 @@ impl Workspace {
 
     pub fn remove_window(&mut self, window_id: WindowId) {
         self.tiled_windows.retain(|&id| id != window_id);
-        self.layout.remove_window(window_id);
+        self.remove_tiled(window_id);
         self.floating_windows.retain(|&id| id != window_id);
         self.focus_stack.retain(|&id| id != window_id);
         if self.fullscreen_window == Some(window_id) {
@@ -76,19 +224,95 @@ impl Workspace {
         }
     }
 
+    /// Detach a tiled container along with the windows below it.
+    pub fn take_container(&mut self, container_id: ContainerId) -> Option<Subtree> {
+        let subtree = self.layout.detach_subtree(container_id)?;
+        self.tiled_windows.retain(|id| !subtree.windows.contains(id));
+        self.focus_stack.retain(|id| !subtree.windows.contains(id));
+        if self
+            .fullscreen_window
+            .is_some_and(|id| subtree.windows.contains(&id))
+        {
+            self.fullscreen_window = None;
+        }
+        Some(subtree)
+    }
+
+    /// Attach a container taken from another workspace beside the focused window.
+    /// A scrolling workspace gives each of its windows a column instead.
+    ///
+    /// Returns the new ID of each container that was kept.
+    pub fn add_container(&mut self, subtree: Subtree) -> HashMap<ContainerId, ContainerId> {
+        self.tiled_windows.extend(&subtree.windows);
+        self.focus_stack.extend(&subtree.windows);
+        let ids = if let Some(scrolling) = &mut self.scrolling {
+            for &window_id in &subtree.windows {
+                scrolling.add_window(window_id);
+            }
+            HashMap::new()
+        } else {
+            self.layout.attach_subtree(subtree)
+        };
+        if let Some(focused) = self.focused_tiled_window() {
+            self.focus_stack.retain(|&id| id != focused);
+            self.focus_stack.push(focused);
+        }
+        ids
+    }
+
+    /// Add the containers and placeholders of a saved layout beside the
+    /// focused window, leaving focus where it was.
+    pub fn append_layout(&mut self, subtree: Subtree) {
+        let focused = self.layout.focused_window();
+        self.layout.attach_subtree(subtree);
+        if let Some(focused) = focused {
+            self.layout.focus_window(focused);
+        }
+    }
+
+    /// The first placeholder waiting for `window`, in the set-aside tree
+    /// while the workspace scrolls.
+    #[must_use]
+    pub fn placeholder_for(&self, window: &Window) -> Option<ContainerId> {
+        self.suspended_layout
+            .as_ref()
+            .unwrap_or(&self.layout)
+            .placeholder_for(window)
+    }
+
+    /// Fill the placeholder `container_id` with `window_id`. While the
+    /// workspace scrolls the window also gets a column of its own.
+    pub fn swallow_window(&mut self, window_id: WindowId, container_id: ContainerId) -> bool {
+        let layout = self.suspended_layout.as_mut().unwrap_or(&mut self.layout);
+        if !layout.swallow(container_id, window_id) {
+            return false;
+        }
+        if let Some(scrolling) = &mut self.scrolling {
+            scrolling.add_window(window_id);
+        }
+        self.tiled_windows.push(window_id);
+        self.focus_stack.push(window_id);
+        true
+    }
+
     pub fn float_window(&mut self, window_id: WindowId) {
         if let Some(pos) = self.tiled_windows.iter().position(|&id| id == window_id) {
             self.tiled_windows.remove(pos);
-            self.layout.remove_window(window_id);
+            self.remove_tiled(window_id);
             self.floating_windows.push(window_id);
         }
     }
 
-    pub fn tile_window(&mut self, window_id: WindowId, config: &Config) {
+    pub fn tile_window(
+        &mut self,
+        window_id: WindowId,
+        placement: NewWindowPlacement,
+        config: &Config,
+    ) {
         if let Some(pos) = self.floating_windows.iter().position(|&id| id == window_id) {
             self.floating_windows.remove(pos);
             self.tiled_windows.push(window_id);
-            self.layout.add_window(window_id, config);
+            self.insert_tiled(window_id, placement, config);
         }
     }
 
@@ -107,6 +331,22 @@ impl Workspace {
         self.tiled_windows.is_empty() && self.floating_windows.is_empty()
     }
 
+    /// Whether nothing would be lost by destroying the workspace: no
+    /// windows and no placeholders waiting for one.
+    #[must_use]
+    pub fn is_disposable(&self) -> bool {
+        self.is_empty()
+            && self.layout.root.is_none()
+            && self.suspended_layout.as_ref().map_or(true, |layout| layout.root.is_none())
+    }
+
+    /// Workspaces are kept in order of number, those without one last,
+    /// and then of name.
+    #[must_use]
+    pub fn sort_key(&self) -> (bool, Option<u32>, &str) {
+        (self.number.is_none(), self.number, &self.name)
+    }
+
     pub fn contains(&self, window_id: WindowId) -> bool {
         self.tiled_windows.contains(&window_id) || self.floating_windows.contains(&window_id)
     }
@@ -118,14 +358,162 @@ impl Workspace {
     pub fn focus_window(&mut self, window_id: WindowId) {
         self.focus_stack.retain(|&id| id != window_id);
         self.focus_stack.push(window_id);
+        self.focus_tiled(window_id);
+    }
+
+    /// Tiled windows a layout pass shows. A scrolling workspace counts all
+    /// of its columns.
+    #[must_use]
+    pub fn visible_tiled_count(&self) -> usize {
+        if self.scrolling.is_some() {
+            self.tiled_windows.len()
+        } else {
+            self.layout.visible_window_count()
+        }
+    }
+
+    /// Gaps in effect here: a runtime override, else the workspace's
+    /// config entry, else the global setting.
+    #[must_use]
+    pub fn gaps(&self, config: &Config) -> GapConfig {
+        self.gaps.clone().unwrap_or_else(|| {
+            config
+                .workspace_config(&self.name)
+                .and_then(|entry| entry.gaps)
+                .map_or_else(|| config.gaps.clone(), GapConfig::uniform)
+        })
+    }
+
+    /// Whether `smart_gaps` drops the gaps: a single visible tiled window.
+    fn gaps_suppressed(&self, config: &Config) -> bool {
+        config.general.smart_gaps && self.visible_tiled_count() <= 1
+    }
+
+    fn gaps_active(&self, config: &Config) -> bool {
+        let gaps = self.gaps(config);
+        !self.gaps_suppressed(config)
+            && (gaps.inner > 0 || gaps.outer_edges().iter().any(|&edge| edge > 0))
     }
 
-    pub fn calculate_layout(&mut self, outer_gap: u32) {
-        self.layout.calculate_layout(self.work_area, outer_gap);
+    /// Lay out the tiled windows, honouring the size hints recorded in
+    /// `windows`.
+    pub fn calculate_layout(&mut self, config: &Config, windows: &HashMap<WindowId, Window>) {
+        let gaps = self.gaps(config);
+        let [top, right, bottom, left] = if self.gaps_suppressed(config) {
+            [0; 4]
+        } else {
+            gaps.outer_edges()
+        };
+        let area = self.work_area;
+        let inner = Geometry::new(
+            area.x + left as i32,
+            area.y + top as i32,
+            area.width.saturating_sub(left + right),
+            area.height.saturating_sub(top + bottom),
+        );
+        if let Some(scrolling) = &mut self.scrolling {
+            scrolling.gap = gaps.inner;
+            scrolling.calculate_layout(inner, &mut self.viewport_offset);
+        } else {
+            self.layout.set_inner_gap(gaps.inner);
+            self.layout.header_height = config.font.title_bar_height();
+            for &id in &self.tiled_windows {
+                if let Some(window) = windows.get(&id) {
+                    self.layout.set_size_hints(id, window.size_hints);
+                }
+            }
+            self.layout.calculate_layout(inner, 0);
+        }
     }
 
     pub fn window_geometry(&self, window_id: WindowId) -> Option<Geometry> {
-        self.layout.window_geometries.get(&window_id).copied()
+        self.scrolling
+            .as_ref()
+            .map_or(&self.layout.window_geometries, |scrolling| {
+                &scrolling.window_geometries
+            })
+            .get(&window_id)
+            .copied()
+    }
+
+    /// Tab and stack headers of the last layout, with `focused` as the
+    /// focused window. Scrolling workspaces have none.
+    #[must_use]
+    pub fn headers(
+        &self,
+        windows: &HashMap<WindowId, Window>,
+        focused: Option<WindowId>,
+        config: &Config,
+    ) -> Vec<Header> {
+        if self.is_scrolling() {
+            return Vec::new();
+        }
+        decoration::headers(&self.layout, windows, focused, &config.colors)
+    }
+
+    /// Height of `window`'s own title bar. A window in a tabbed or stacked
+    /// container has none, its header standing in for it.
+    #[must_use]
+    pub fn title_bar_height(&self, window: &Window, config: &Config) -> u32 {
+        let in_tabs = self
+            .layout
+            .container_of(window.id)
+            .and_then(|id| self.layout.containers.get(id))
+            .is_some_and(|parent| {
+                matches!(parent.layout, LayoutMode::Tabbed | LayoutMode::Stacked)
+            });
+        if in_tabs {
+            0
+        } else {
+            window.title_bar_height(&config.font)
+        }
+    }
+
+    /// The border of tiled `window_id` when drawn `width` wide, with
+    /// `smart_borders` and `hide_edge_borders` applied to its last layout.
+    #[must_use]
+    pub fn window_borders(&self, window_id: WindowId, width: u32, config: &Config) -> Borders {
+        let single = self.visible_tiled_count() <= 1;
+        let gaps = self.gaps_active(config);
+        let hidden = match config.general.smart_borders {
+            SmartBorders::Off => false,
+            SmartBorders::On => single,
+            SmartBorders::NoGaps => single || gaps,
+        };
+        if hidden {
+            return Borders::default();
+        }
+
+        let mut borders = Borders::uniform(width);
+        let Some(geometry) = self.window_geometry(window_id) else {
+            return borders;
+        };
+        let (vertical, horizontal) = match config.general.hide_edge_borders {
+            HideEdgeBorders::None => (false, false),
+            HideEdgeBorders::Vertical => (true, false),
+            HideEdgeBorders::Horizontal => (false, true),
+            HideEdgeBorders::Both => (true, true),
+            HideEdgeBorders::Smart => (single, single),
+            HideEdgeBorders::SmartNoGaps => (single && !gaps, single && !gaps),
+        };
+        let area = self.work_area;
+        if vertical {
+            if geometry.x <= area.x {
+                borders.left = 0;
+            }
+            if geometry.x + geometry.width as i32 >= area.x + area.width as i32 {
+                borders.right = 0;
+            }
+        }
+        if horizontal {
+            if geometry.y <= area.y {
+                borders.top = 0;
+            }
+            if geometry.y + geometry.height as i32 >= area.y + area.height as i32 {
+                borders.bottom = 0;
+            }
+        }
+        borders
     }
 
     pub fn set_geometry(&mut self, geometry: Geometry) {
@@ -137,6 +525,16 @@ impl Workspace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Orientation;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("3"), Some(3));
+        assert_eq!(parse_number("3:mail"), Some(3));
+        assert_eq!(parse_number("12web"), Some(12));
+        assert_eq!(parse_number("mail"), None);
+        assert_eq!(parse_number(""), None);
+    }
 
     #[test]
     fn test_workspace_creation() {
@@ -165,6 +563,83 @@ mod tests {
         assert!(!ws.contains(w1));
     }
 
+    #[test]
+    fn test_new_root_follows_config_and_aspect() {
+        let mut config = Config::default();
+        config.general.default_layout = LayoutMode::Tabbed;
+        let mut ws = Workspace::new(WorkspaceId(1), "1".to_string());
+        ws.set_geometry(Geometry::new(0, 0, 1080, 1920));
+
+        ws.add_window(WindowId(100), &config);
+        let root = &ws.layout.containers[ws.layout.root.unwrap()];
+        assert_eq!(root.layout, LayoutMode::Tabbed);
+        assert_eq!(root.split_direction, SplitDirection::Vertical);
+
+        ws.remove_window(WindowId(100));
+        config.general.default_orientation = Orientation::Horizontal;
+        ws.add_window(WindowId(101), &config);
+        let root = &ws.layout.containers[ws.layout.root.unwrap()];
+        assert_eq!(root.split_direction, SplitDirection::Horizontal);
+    }
+
+    #[test]
+    fn test_smart_gaps_and_borders() {
+        let mut config = Config::default();
+        config.general.smart_gaps = true;
+        config.general.smart_borders = SmartBorders::On;
+        config.general.hide_edge_borders = HideEdgeBorders::Vertical;
+        config.gaps.inner = 0;
+        config.gaps.outer = 10;
+        let mut ws = Workspace::new(WorkspaceId(1), "1".to_string());
+        ws.set_geometry(Geometry::new(0, 0, 1000, 800));
+
+        // A lone window fills the workspace without gaps or borders.
+        ws.add_window(WindowId(100), &config);
+        ws.calculate_layout(&config, &HashMap::new());
+        assert_eq!(ws.window_geometry(WindowId(100)), Some(ws.work_area));
+        assert_eq!(
+            ws.window_borders(WindowId(100), 2, &config),
+            Borders::default()
+        );
+
+        // With two, the outer gap returns and keeps windows off the edges.
+        ws.add_window(WindowId(101), &config);
+        ws.calculate_layout(&config, &HashMap::new());
+        assert_eq!(ws.window_geometry(WindowId(100)).unwrap().x, 10);
+        assert_eq!(
+            ws.window_borders(WindowId(100), 2, &config),
+            Borders::uniform(2)
+        );
+
+        config.gaps.outer = 0;
+        ws.calculate_layout(&config, &HashMap::new());
+        let borders = ws.window_borders(WindowId(100), 2, &config);
+        assert_eq!((borders.left, borders.right, borders.top), (0, 2, 2));
+    }
+
+    #[test]
+    fn test_column_ids_are_workspace_container_ids() {
+        let config = Config::default();
+        let mut ids = Vec::new();
+        for id in 1..=2 {
+            let mut ws = Workspace::new(WorkspaceId(id), id.to_string());
+            ws.add_window(WindowId(1), &config);
+            let tree_ids: Vec<_> = ws.layout.containers.values().map(|c| c.id).collect();
+            ws.set_scrolling(true, &config);
+            ws.add_window(WindowId(2), &config);
+
+            let scrolling = ws.scrolling.as_ref().unwrap();
+            for column in &scrolling.columns {
+                assert!(!tree_ids.contains(&column.id));
+                assert!(column.id.0 > u64::from(u32::MAX));
+                ids.push(column.id);
+            }
+        }
+        // No column ID turns up twice, across workspaces or within one.
+        let unique: std::collections::HashSet<_> = ids.iter().collect();
+        assert_eq!(unique.len(), 4);
+    }
+
     #[test]
     fn test_focus_stack() {
         let config = Config::default();
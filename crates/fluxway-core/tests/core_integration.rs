@@ -5,10 +5,16 @@
 //! using only plain Rust types.
 
 use fluxway_core::config::Config;
+use fluxway_core::decoration::TabState;
 use fluxway_core::event::{CoreAction, CoreEvent};
-use fluxway_core::input::{Command, Toggle, WorkspaceTarget};
+use fluxway_core::input::{
+    Command, FocusTarget, LayoutCmd, MoveTarget, ResizeAmount, ResizeDirection, ResizeOp,
+    SplitCmd, Toggle, WorkspaceTarget,
+};
+use fluxway_core::layout::LayoutNode;
+use fluxway_core::saved_layout::LayoutSpec;
 use fluxway_core::state::Geometry;
-use fluxway_core::window::WindowState;
+use fluxway_core::window::{BorderStyle, SizeHints, WindowState};
 use fluxway_core::Core;
 
 /// Helper: create a core with default config and a 1920×1080 output.
@@ -96,19 +102,50 @@ fn focus_movement_across_tiled_windows() {
     assert!(core.state.windows.get(&w2).unwrap().is_tiled());
     assert!(core.state.windows.get(&w3).unwrap().is_tiled());
 
-    // Relayout should produce geometry for all three
+    // Each tiled window has been laid out.
+    let ws = core.state.focused_workspace().unwrap();
+    assert!([w1, w2, w3]
+        .iter()
+        .all(|&id| ws.window_geometry(id).is_some()));
+
+    // Mapping already sent their geometry, so a tick has nothing new to
+    // send.
     let tick_actions = core.tick();
     let geo_actions: Vec<_> = tick_actions
         .iter()
         .filter(|a| matches!(a, CoreAction::SetWindowGeometry { .. }))
         .collect();
+    assert!(
+        geo_actions.is_empty(),
+        "Expected no geometry for unmoved windows, got {geo_actions:?}"
+    );
 
-    // Each tiled window should have a geometry action
+    // Directional focus walks the row: w1 | w2 | w3.
+    let actions = core.exec(Command::Focus(FocusTarget::Left));
+    assert_eq!(core.focused_window(), Some(w2));
     assert!(
-        geo_actions.len() >= 3,
-        "Expected geometry for 3 windows, got {} actions: {geo_actions:?}",
-        geo_actions.len()
+        actions.iter().any(|a| matches!(a, CoreAction::SetFocus { id: Some(id) } if *id == w2)),
+        "Expected SetFocus for {w2}, got: {actions:?}"
     );
+    core.exec(Command::Focus(FocusTarget::Left));
+    assert_eq!(core.focused_window(), Some(w1));
+
+    // Default focus_wrapping wraps from the left edge to the right edge.
+    core.exec(Command::Focus(FocusTarget::Left));
+    assert_eq!(core.focused_window(), Some(w3));
+
+    // Nest w4 below w3: up/down stay inside the split, left leaves it,
+    // and coming back right re-enters at the split's focused child.
+    core.exec(Command::Split(SplitCmd::Vertical));
+    let w4 = map_window(&mut core, "app4", "App 4");
+    core.exec(Command::Focus(FocusTarget::Up));
+    assert_eq!(core.focused_window(), Some(w3));
+    core.exec(Command::Focus(FocusTarget::Down));
+    assert_eq!(core.focused_window(), Some(w4));
+    core.exec(Command::Focus(FocusTarget::Left));
+    assert_eq!(core.focused_window(), Some(w2));
+    core.exec(Command::Focus(FocusTarget::Right));
+    assert_eq!(core.focused_window(), Some(w4));
 }
 
 // ── Test 3: toggle floating preserves geometry and focus ─────────
@@ -258,7 +295,6 @@ fn move_window_to_workspace() {
 
     let w1 = map_window(&mut core, "app", "App");
     let ws1 = *core.state.workspaces.keys().next().unwrap();
-    let ws3 = *core.state.workspaces.keys().nth(2).unwrap();
 
     // Window starts on workspace 1
     assert_eq!(core.state.windows.get(&w1).unwrap().workspace, Some(ws1));
@@ -267,7 +303,8 @@ fn move_window_to_workspace() {
     core.exec(Command::MoveToWorkspace(WorkspaceTarget::Number(3)));
 
     // Window should now be on workspace 3
-    assert_eq!(core.state.windows.get(&w1).unwrap().workspace, Some(ws3));
+    let ws3 = core.state.windows.get(&w1).unwrap().workspace.unwrap();
+    assert_eq!(core.state.workspaces[&ws3].name, "3");
     assert!(core.state.workspaces.get(&ws3).unwrap().contains(w1));
     assert!(!core.state.workspaces.get(&ws1).unwrap().contains(w1));
 }
@@ -311,3 +348,1036 @@ fn invariants_hold_after_mixed_operations() {
     // Validate invariants
     core.state.validate_invariants().expect("Invariants should hold");
 }
+
+// ── Test 10: split nests new windows ─────────────────────────────
+
+#[test]
+fn split_nests_new_windows_beside_focused() {
+    let mut core = test_core();
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    core.exec(Command::Split(SplitCmd::Vertical));
+    let w3 = map_window(&mut core, "c", "C");
+
+    let ws = core.state.focused_workspace().unwrap();
+    let g1 = ws.window_geometry(w1).unwrap();
+    let g2 = ws.window_geometry(w2).unwrap();
+    let g3 = ws.window_geometry(w3).unwrap();
+
+    // w1 keeps the left half; w2 and w3 share the right half top/bottom.
+    assert!(g1.x < g2.x);
+    assert_eq!(g2.x, g3.x);
+    assert_eq!(g2.width, g3.width);
+    assert!(g2.y < g3.y);
+    assert_eq!(g1.height, g2.height + g3.height + core.state.config.gaps.inner);
+}
+
+// ── Test 11: layout switching on the focused container ───────────
+
+#[test]
+fn layout_commands_switch_focused_container() {
+    let mut core = test_core();
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+
+    core.exec(Command::Layout(LayoutCmd::Tabbed));
+    let ws = core.state.focused_workspace().unwrap();
+    let tab = ws.window_geometry(w2).unwrap();
+    assert!(ws.window_geometry(w1).is_none(), "Hidden tab should not be laid out");
+    assert_eq!(tab.width, 1920 - 2 * core.state.config.gaps.outer);
+
+    // toggle split leaves tabbed for the last split layout (horizontal)...
+    core.exec(Command::Layout(LayoutCmd::ToggleSplit));
+    let ws = core.state.focused_workspace().unwrap();
+    let (g1, g2) = (ws.window_geometry(w1).unwrap(), ws.window_geometry(w2).unwrap());
+    assert!(g1.x < g2.x);
+    assert_eq!(g1.y, g2.y);
+
+    // ...and then flips it to vertical.
+    core.exec(Command::Layout(LayoutCmd::ToggleSplit));
+    let ws = core.state.focused_workspace().unwrap();
+    let (g1, g2) = (ws.window_geometry(w1).unwrap(), ws.window_geometry(w2).unwrap());
+    assert_eq!(g1.x, g2.x);
+    assert!(g1.y < g2.y);
+}
+
+// ── Test 12: directional move restructures the tree ──────────────
+
+#[test]
+fn move_window_swaps_and_keeps_focus() {
+    let mut core = test_core();
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    let before = core.state.focused_workspace().unwrap().window_geometry(w1).unwrap();
+
+    let actions = core.exec(Command::Move(MoveTarget::Left));
+    assert!(
+        actions.iter().any(|a| matches!(
+            a,
+            CoreAction::SetWindowGeometry { id, x, .. } if *id == w2 && *x == before.x
+        )),
+        "Expected {w2} to take {w1}'s slot, got: {actions:?}"
+    );
+    assert_eq!(core.focused_window(), Some(w2));
+
+    // Moving down out of a horizontal row stacks the window above the rest.
+    core.exec(Command::Move(MoveTarget::Down));
+    let ws = core.state.focused_workspace().unwrap();
+    let (g1, g2) = (ws.window_geometry(w1).unwrap(), ws.window_geometry(w2).unwrap());
+    assert_eq!(g1.x, g2.x);
+    assert!(g2.y > g1.y);
+    assert_eq!(core.focused_window(), Some(w2));
+}
+
+// ── Test 13: resizing tiled and floating windows ─────────────────
+
+#[test]
+fn resize_tiled_and_floating_windows() {
+    let mut core = test_core();
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    let width = |core: &Core, id| {
+        core.state.focused_workspace().unwrap().window_geometry(id).unwrap().width
+    };
+    let (w1_before, w2_before) = (width(&core, w1), width(&core, w2));
+
+    // w2 is the last child, so growing its width takes space from w1.
+    core.exec(Command::Resize(
+        ResizeDirection::Width(ResizeOp::Grow),
+        ResizeAmount::Px(100),
+    ));
+    assert!((i64::from(width(&core, w2)) - i64::from(w2_before) - 100).abs() <= 1);
+    assert!((i64::from(w1_before) - i64::from(width(&core, w1)) - 100).abs() <= 1);
+
+    // Percentage points are relative to the parent split.
+    core.exec(Command::parse("resize set width 25 ppt"));
+    let total = width(&core, w1) + width(&core, w2);
+    assert!((f64::from(width(&core, w2)) / f64::from(total) - 0.25).abs() < 0.01);
+
+    // Floating windows are resized directly.
+    core.exec(Command::Floating(Toggle::Enable));
+    let before = core.state.windows[&w2].geometry;
+    let actions = core.exec(Command::parse("resize grow left 50 px"));
+    let after = core.state.windows[&w2].geometry;
+    assert_eq!(after.width, before.width + 50);
+    assert_eq!(after.x, before.x - 50);
+    assert!(actions
+        .iter()
+        .any(|a| matches!(a, CoreAction::SetWindowGeometry { id, w, .. } if *id == w2 && *w == after.width)));
+}
+
+// ── Test 14: focus parent selects and acts on containers ─────────
+
+#[test]
+fn focus_parent_selects_and_acts_on_containers() {
+    let mut core = test_core();
+
+    let w0 = map_window(&mut core, "a", "A");
+    let w1 = map_window(&mut core, "b", "B");
+    core.exec(Command::Split(SplitCmd::Vertical));
+    let w2 = map_window(&mut core, "c", "C");
+
+    let ws1 = core.focused_workspace().unwrap();
+    let layout = &core.state.workspaces[&ws1].layout;
+    let root = layout.root.unwrap();
+    let nested = layout.container_of(w2).unwrap();
+    assert_eq!(layout.container_of(w1), Some(nested));
+
+    // `focus parent` selects the split holding w1 and w2; input focus stays on w2.
+    core.exec(Command::Focus(FocusTarget::Parent));
+    assert_eq!(core.state.focus.focused_container, Some(nested));
+    assert_eq!(core.focused_window(), Some(w2));
+
+    // `kill` closes every window in the subtree.
+    let closed: Vec<_> = core
+        .exec(Command::Kill)
+        .into_iter()
+        .filter_map(|a| match a {
+            CoreAction::RequestClose { id } => Some(id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(closed, vec![w1, w2]);
+
+    // Up to the workspace root and back down again.
+    core.exec(Command::Focus(FocusTarget::Parent));
+    assert_eq!(core.state.focus.focused_container, Some(root));
+    core.exec(Command::Focus(FocusTarget::Child));
+    assert_eq!(core.state.focus.focused_container, Some(nested));
+
+    core.exec(Command::Mark("pair".into()));
+    assert_eq!(core.state.container_marks.get("pair"), Some(&nested));
+
+    // The whole container moves, keeping its structure. It is numbered
+    // anew in the target's tree, and its mark follows it.
+    core.exec(Command::MoveToWorkspace(WorkspaceTarget::Number(2)));
+    let (&ws2, workspace) = core.state.workspaces.get_index(1).unwrap();
+    let nested = workspace.layout.root.unwrap();
+    assert_eq!(workspace.layout.container_of(w1), Some(nested));
+    assert_eq!(core.state.container_marks.get("pair"), Some(&nested));
+    assert_eq!(workspace.tiled_windows, vec![w1, w2]);
+    assert_eq!(core.state.workspaces[&ws1].tiled_windows, vec![w0]);
+    assert_eq!(core.state.windows[&w1].workspace, Some(ws2));
+
+    // Jumping to the mark follows the container and selects it again.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(1)));
+    assert_eq!(core.state.focus.focused_container, None);
+    core.exec(Command::GotoMark("pair".into()));
+    assert_eq!(core.focused_workspace(), Some(ws2));
+    assert_eq!(core.state.focus.focused_container, Some(nested));
+    assert_eq!(core.focused_window(), Some(w2));
+    assert!(core.state.validate_invariants().is_ok());
+}
+
+// ── Test 15: master–stack layout commands ────────────────────────
+
+#[test]
+fn master_stack_commands_arrange_windows() {
+    let mut core = test_core();
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    let w3 = map_window(&mut core, "c", "C");
+    core.exec(Command::parse("layout master_stack"));
+
+    let geometry = |core: &Core, id| {
+        core.state.focused_workspace().unwrap().window_geometry(id).unwrap()
+    };
+    let master = geometry(&core, w1);
+    assert!(master.width > geometry(&core, w2).width);
+    assert_eq!(geometry(&core, w2).x, geometry(&core, w3).x);
+    assert!(geometry(&core, w2).y < geometry(&core, w3).y);
+
+    core.exec(Command::parse("master ratio +0.05"));
+    assert!(geometry(&core, w1).width > master.width);
+
+    // The focused window (w3) becomes the master.
+    core.exec(Command::parse("swap master"));
+    assert_eq!(geometry(&core, w3).x, master.x);
+    assert_eq!(core.focused_window(), Some(w3));
+
+    // Swapping exchanged w1 and w3, so w2 joins w3 in the master column.
+    core.exec(Command::parse("master count +1"));
+    assert_eq!(geometry(&core, w2).x, geometry(&core, w3).x);
+    assert!(geometry(&core, w1).x > geometry(&core, w3).x);
+
+    // Resizing moves the border between the master and stack areas.
+    let width = geometry(&core, w3).width;
+    core.exec(Command::parse("resize grow width 10 ppt"));
+    assert!(geometry(&core, w3).width > width);
+    core.exec(Command::parse("focus right"));
+    assert_eq!(core.focused_window(), Some(w1));
+    core.exec(Command::parse("resize grow width 20 ppt"));
+    assert!(geometry(&core, w3).width < width);
+    core.exec(Command::parse("resize set width 50 ppt"));
+    assert_eq!(geometry(&core, w1).width, geometry(&core, w3).width);
+}
+
+// ── Test 16: per-workspace automatic layouts ─────────────────────
+
+#[test]
+fn workspace_config_selects_grid_layout() {
+    let mut config: Config = toml::from_str(
+        r#"
+        [[workspaces]]
+        name = "1"
+        layout = "grid"
+        "#,
+    )
+    .unwrap();
+    config.gaps.inner = 0;
+    config.gaps.outer = 0;
+    let mut core = Core::new(config);
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "test-output".into(),
+        geometry: Geometry::new(0, 0, 1920, 1080),
+    });
+
+    let windows: Vec<_> = (0..4).map(|_| map_window(&mut core, "term", "Term")).collect();
+    let ws = core.state.focused_workspace().unwrap();
+    let geometries: Vec<_> = windows
+        .iter()
+        .map(|&id| ws.window_geometry(id).unwrap())
+        .collect();
+    assert_eq!(geometries[0], Geometry::new(0, 0, 960, 540));
+    assert_eq!(geometries[3], Geometry::new(960, 540, 960, 540));
+
+    // `layout dwindle` switches the root container at runtime.
+    core.exec(Command::parse("layout dwindle"));
+    let ws = core.state.focused_workspace().unwrap();
+    assert_eq!(ws.window_geometry(windows[0]).unwrap(), Geometry::new(0, 0, 960, 1080));
+}
+
+// ── Test 17: scrollable column layout ───────────────────────────
+
+#[test]
+fn scrolling_layout_hides_windows_outside_viewport() {
+    let mut config = Config::default();
+    config.gaps.inner = 0;
+    config.gaps.outer = 0;
+    let mut core = Core::new(config);
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "test-output".into(),
+        geometry: Geometry::new(0, 0, 1920, 1080),
+    });
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    let w3 = map_window(&mut core, "c", "C");
+
+    // Three half-width columns overflow the output; the viewport follows
+    // the focused column (w3) and w1 scrolls out of view.
+    let actions = core.exec(Command::parse("layout scrolling"));
+    assert!(actions.contains(&CoreAction::SetVisible { id: w1, visible: false }));
+    let ws = core.state.focused_workspace().unwrap();
+    assert_eq!(ws.viewport_offset, 960);
+    assert_eq!(ws.window_geometry(w3).unwrap(), Geometry::new(960, 0, 960, 1080));
+    assert_eq!(ws.window_geometry(w2).unwrap().x, 0);
+
+    let actions = core.exec(Command::parse("focus column left"));
+    assert_eq!(core.focused_window(), Some(w2));
+    assert!(!actions.iter().any(|a| matches!(a, CoreAction::SetVisible { .. })));
+
+    let actions = core.exec(Command::parse("focus column left"));
+    assert_eq!(core.focused_window(), Some(w1));
+    assert!(actions.contains(&CoreAction::SetVisible { id: w1, visible: true }));
+    assert!(actions.contains(&CoreAction::SetVisible { id: w3, visible: false }));
+    assert!(core.state.windows[&w3].state.contains(WindowState::HIDDEN));
+
+    // 1/2 → 2/3 of the output.
+    core.exec(Command::parse("column width cycle"));
+    let ws = core.state.focused_workspace().unwrap();
+    assert_eq!(ws.window_geometry(w1).unwrap().width, 1280);
+    assert_eq!(ws.window_geometry(w2).unwrap().x, 1280);
+
+    // Leaving the scrolling layout restores the tree with every window shown.
+    core.exec(Command::parse("layout splith"));
+    let ws = core.state.focused_workspace().unwrap();
+    assert!(!ws.is_scrolling());
+    assert_eq!(ws.window_geometry(w3).unwrap(), Geometry::new(1280, 0, 640, 1080));
+}
+
+#[test]
+fn leaving_scrolling_layout_restores_containers_and_marks() {
+    let mut core = test_core();
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    core.exec(Command::parse("split v"));
+    let w3 = map_window(&mut core, "c", "C");
+    core.exec(Command::parse("focus parent"));
+    core.exec(Command::Mark("pair".into()));
+    let pair = core.state.container_marks["pair"];
+
+    core.exec(Command::parse("layout scrolling"));
+    let w4 = map_window(&mut core, "d", "D");
+    core.exec(Command::parse("layout splith"));
+    core.exec(Command::parse("split v"));
+
+    // The split and its mark survive; the window opened while scrolling
+    // joins the tree.
+    let ws = core.state.focused_workspace().unwrap();
+    let root = ws.layout.root.unwrap();
+    assert_eq!(ws.layout.container_of(w1), Some(root));
+    assert_eq!(ws.layout.container_of(w2), Some(pair));
+    assert_eq!(ws.layout.container_of(w3), Some(pair));
+    assert!(ws.layout.container_of(w4).is_some());
+    assert_eq!(core.state.container_marks["pair"], pair);
+    core.state.validate_invariants().expect("Invariants should hold");
+}
+
+// ── Test 18: append_layout placeholders swallow windows ──────────
+
+#[test]
+fn append_layout_placeholders_swallow_mapped_windows() {
+    let mut config = Config::default();
+    config.gaps.inner = 0;
+    config.gaps.outer = 0;
+    let mut core = Core::new(config);
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "test-output".into(),
+        geometry: Geometry::new(0, 0, 1920, 1080),
+    });
+
+    let path = std::env::temp_dir().join(format!("fluxway-layout-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{
+            "layout": "split",
+            "orientation": "horizontal",
+            "nodes": [
+                { "percent": 0.6, "swallows": { "app_id": "editor" } },
+                {
+                    "percent": 0.4,
+                    "orientation": "vertical",
+                    "nodes": [
+                        { "swallows": { "app_id": "term" } },
+                        { "swallows": { "app_id": "term" } }
+                    ]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    core.exec(Command::parse(&format!("append_layout {}", path.display())));
+    std::fs::remove_file(&path).unwrap();
+
+    // Mapped out of order, each window still lands in its own slot.
+    let t1 = map_window(&mut core, "term", "Term 1");
+    let editor = map_window(&mut core, "editor", "Editor");
+    let t2 = map_window(&mut core, "term", "Term 2");
+
+    let ws = core.state.focused_workspace().unwrap();
+    assert_eq!(ws.window_geometry(editor).unwrap(), Geometry::new(0, 0, 1152, 1080));
+    assert_eq!(ws.window_geometry(t1).unwrap(), Geometry::new(1152, 0, 768, 540));
+    assert_eq!(ws.window_geometry(t2).unwrap(), Geometry::new(1152, 540, 768, 540));
+    assert_eq!(core.focused_window(), Some(t2));
+
+    // Windows without a placeholder open beside the focused one as usual.
+    let other = map_window(&mut core, "browser", "Browser");
+    let ws = core.state.focused_workspace().unwrap();
+    assert_eq!(ws.window_geometry(other).unwrap().x, 1152);
+
+    // Saving the filled tree records the windows as criteria again.
+    let spec = LayoutSpec::from_tree(&ws.layout, &core.state.windows).unwrap();
+    let swallows = spec.nodes[0].swallows.as_ref().unwrap();
+    assert_eq!(swallows.app_id.as_deref(), Some("editor"));
+
+    // While the workspace scrolls, placeholders in the set-aside tree
+    // still take their windows, which keep them once scrolling stops.
+    std::fs::write(&path, r#"{ "swallows": { "app_id": "mail" } }"#).unwrap();
+    core.exec(Command::parse(&format!("append_layout {}", path.display())));
+    std::fs::remove_file(&path).unwrap();
+    core.exec(Command::parse("layout scrolling"));
+    let mail = map_window(&mut core, "mail", "Mail");
+    assert!(core.state.focused_workspace().unwrap().window_geometry(mail).is_some());
+    core.exec(Command::parse("layout splith"));
+    let ws = core.state.focused_workspace().unwrap();
+    assert!(ws.layout.containers.values().all(|c| !c.is_placeholder()));
+    core.state.validate_invariants().expect("Invariants should hold");
+}
+
+// ── Test 19: runtime gaps, per edge and per workspace ────────────
+
+#[test]
+fn gaps_command_changes_current_or_all_workspaces() {
+    let mut core = test_core();
+    let w1 = map_window(&mut core, "a", "A");
+    map_window(&mut core, "b", "B");
+    let geometry = |core: &Core, id| {
+        core.state.focused_workspace().unwrap().window_geometry(id).unwrap()
+    };
+    assert_eq!(geometry(&core, w1).x, 4);
+
+    core.exec(Command::parse("gaps outer current set 20"));
+    core.exec(Command::parse("gaps top current set 0"));
+    let g = geometry(&core, w1);
+    assert_eq!((g.x, g.y, g.height), (20, 0, 1060));
+
+    // Toggling a non-zero gap clears it.
+    core.exec(Command::parse("gaps horizontal current toggle 50"));
+    assert_eq!(geometry(&core, w1).x, 0);
+
+    // Other workspaces keep the global gaps.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(2)));
+    let w3 = map_window(&mut core, "c", "C");
+    assert_eq!(geometry(&core, w3), Geometry::new(4, 4, 1912, 1072));
+
+    // `all` changes the global value and drops workspace 1's override.
+    core.exec(Command::parse("gaps outer all plus 6"));
+    assert_eq!(geometry(&core, w3).x, 10);
+    core.exec(Command::Workspace(WorkspaceTarget::Number(1)));
+    let g = geometry(&core, w1);
+    assert_eq!((g.x, g.y), (10, 10));
+}
+
+// ── Test 20: tiling honours size hints and reports what cannot fit ──
+
+#[test]
+fn size_hints_constrain_tiled_windows() {
+    let mut core = test_core();
+    let a = map_window(&mut core, "a", "A");
+    let b = map_window(&mut core, "b", "B");
+    let hints = |min_width| SizeHints {
+        min_width: Some(min_width),
+        ..SizeHints::default()
+    };
+    let geometry = |core: &Core, id| {
+        core.state
+            .focused_workspace()
+            .unwrap()
+            .window_geometry(id)
+            .unwrap()
+    };
+
+    let actions = core.handle_event(CoreEvent::WindowSizeHints {
+        id: b,
+        hints: hints(1400),
+    });
+    assert!(!actions
+        .iter()
+        .any(|a| matches!(a, CoreAction::WindowDoesNotFit { .. })));
+    let (ga, gb) = (geometry(&core, a), geometry(&core, b));
+    assert_eq!(gb.width, 1400);
+    assert!(ga.x + ga.width as i32 <= gb.x);
+
+    // Together the minimums exceed the output: both are reported, and
+    // still laid out side by side.
+    let actions = core.handle_event(CoreEvent::WindowSizeHints {
+        id: a,
+        hints: hints(1000),
+    });
+    for id in [a, b] {
+        assert!(actions.contains(&CoreAction::WindowDoesNotFit { id }));
+    }
+    let (ga, gb) = (geometry(&core, a), geometry(&core, b));
+    assert!(ga.width < 1000 && gb.width < 1400);
+    assert!(ga.x + ga.width as i32 <= gb.x);
+}
+
+// ── Test 21: frame and client geometry of decorated windows ─────
+
+#[test]
+fn frames_leave_room_for_borders_and_title_bars() {
+    let mut config = Config::default();
+    config.border.style = BorderStyle::Normal;
+    let title_bar = config.font.title_bar_height();
+    let mut core = Core::new(config);
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "test-output".into(),
+        geometry: Geometry::new(0, 0, 1920, 1080),
+    });
+
+    let w1 = core.next_window_id();
+    let actions = core.handle_event(CoreEvent::WindowMapped {
+        id: w1,
+        app_id: Some("a".into()),
+        title: Some("A".into()),
+        pid: None,
+        initial_geometry: None,
+        is_xwayland: false,
+    });
+    let frame = core
+        .state
+        .focused_workspace()
+        .unwrap()
+        .window_geometry(w1)
+        .unwrap();
+    let client = actions
+        .iter()
+        .find_map(|a| match a {
+            CoreAction::SetWindowFrame {
+                id,
+                frame: f,
+                client,
+            } if *id == w1 => {
+                assert_eq!(*f, frame);
+                Some(*client)
+            }
+            _ => None,
+        })
+        .expect("Expected SetWindowFrame for the tiled window");
+
+    // A 2 px border on every side and the title bar above the client.
+    assert_eq!(client.x, frame.x + 2);
+    assert_eq!(client.y, frame.y + 2 + title_bar as i32);
+    assert_eq!(client.width, frame.width - 4);
+    assert_eq!(client.height, frame.height - 4 - title_bar);
+
+    // In a tabbed container the tab header stands in for the title bar.
+    let w2 = map_window(&mut core, "b", "B");
+    let mut actions = core.exec(Command::Layout(LayoutCmd::Tabbed));
+    actions.extend(core.tick());
+    let (frame, client) = actions
+        .iter()
+        .find_map(|a| match a {
+            CoreAction::SetWindowFrame { id, frame, client } if *id == w2 => {
+                Some((*frame, *client))
+            }
+            _ => None,
+        })
+        .expect("Expected SetWindowFrame for the tab");
+    assert_eq!(client.y, frame.y + 2);
+    assert_eq!(client.height, frame.height - 4);
+
+    #[cfg(feature = "ipc")]
+    {
+        let tree = fluxway_core::ipc::get_tree(&core.state);
+        let tab = tree.find_focused().unwrap();
+        assert_eq!(tab.id, w2.0);
+        assert_eq!((tab.window_rect.y, tab.deco_rect.height), (2, 0));
+    }
+}
+
+// ── Test 22: resized and pinned shares survive new windows ───────
+
+#[test]
+fn resize_set_pins_share_across_new_windows() {
+    let mut core = test_core();
+    let w1 = map_window(&mut core, "a", "A");
+    map_window(&mut core, "b", "B");
+    let width = |core: &Core, id| {
+        core.state
+            .focused_workspace()
+            .unwrap()
+            .window_geometry(id)
+            .unwrap()
+            .width
+    };
+
+    // 70% of the output inside the outer gaps, less the inner gaps.
+    let share = |windows: u32| (f64::from(1912 - 4 * (windows - 1)) * 0.7) as u32;
+
+    core.exec(Command::Focus(FocusTarget::Left));
+    core.exec(Command::parse("resize set width 70 ppt"));
+    assert_eq!(width(&core, w1), share(2));
+
+    // New windows split what is left; the pinned window keeps its share.
+    let w3 = map_window(&mut core, "c", "C");
+    assert_eq!(width(&core, w1), share(3));
+    core.handle_event(CoreEvent::WindowUnmapped { id: w3 });
+    assert_eq!(width(&core, w1), share(2));
+}
+
+// ── Test 23: new window placement from config and rules ──────────
+
+#[test]
+fn new_window_placement_follows_config_and_rules() {
+    let config: Config = toml::from_str(
+        r#"
+        [general]
+        new_window_placement = "end"
+
+        [[rules]]
+        criteria = { app_id = "editor" }
+        placement = "start"
+        "#,
+    )
+    .unwrap();
+    let mut core = Core::new(config);
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "test-output".into(),
+        geometry: Geometry::new(0, 0, 1920, 1080),
+    });
+    let order = |core: &Core| {
+        let ws = core.state.focused_workspace().unwrap();
+        ws.layout
+            .windows_in(LayoutNode::Container(ws.layout.root.unwrap()))
+    };
+
+    let a = map_window(&mut core, "a", "A");
+    let b = map_window(&mut core, "b", "B");
+    core.exec(Command::Focus(FocusTarget::Left));
+    let c = map_window(&mut core, "c", "C");
+    let editor = map_window(&mut core, "editor", "Editor");
+    assert_eq!(order(&core), vec![editor, a, b, c]);
+
+    // Windows moved to another workspace are placed the same way.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(2)));
+    let d = map_window(&mut core, "d", "D");
+    core.exec(Command::Workspace(WorkspaceTarget::Number(1)));
+    core.handle_event(CoreEvent::FocusRequested { id: editor });
+    core.exec(Command::MoveToWorkspace(WorkspaceTarget::Number(2)));
+    core.handle_event(CoreEvent::FocusRequested { id: a });
+    core.exec(Command::MoveToWorkspace(WorkspaceTarget::Number(2)));
+    core.exec(Command::Workspace(WorkspaceTarget::Number(2)));
+    assert_eq!(order(&core), vec![editor, d, a]);
+
+    // So are floating windows tiled again.
+    core.handle_event(CoreEvent::FocusRequested { id: editor });
+    core.exec(Command::parse("floating toggle"));
+    assert_eq!(order(&core), vec![d, a]);
+    core.exec(Command::parse("floating toggle"));
+    assert_eq!(order(&core), vec![editor, d, a]);
+}
+
+// ── Test 24: tab and stack headers go out after each layout ──────
+
+#[test]
+fn tabbed_layout_reports_headers() {
+    let mut core = test_core();
+    let headers = |actions: &[CoreAction]| {
+        actions
+            .iter()
+            .rev()
+            .find_map(|action| match action {
+                CoreAction::SetDecorations { headers } => Some(headers.clone()),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let w1 = map_window(&mut core, "a", "Alpha");
+    let w2 = map_window(&mut core, "b", "Beta");
+    let actions = core.exec(Command::Layout(LayoutCmd::Tabbed));
+    let tabbed = headers(&actions);
+    assert_eq!(tabbed.len(), 1);
+    let titles: Vec<&str> = tabbed[0]
+        .tabs
+        .iter()
+        .map(|tab| tab.title.as_str())
+        .collect();
+    assert_eq!(titles, ["Alpha", "Beta"]);
+    assert_eq!(tabbed[0].tabs[1].window, Some(w2));
+    assert_eq!(tabbed[0].tabs[1].state, TabState::Focused);
+    assert_eq!(tabbed[0].tabs[0].state, TabState::Unfocused);
+    assert_eq!(tabbed[0].tabs[1].colors, core.state.config.colors.focused);
+    let header_height = core.state.config.font.title_bar_height();
+    assert_eq!(tabbed[0].geometry.height, header_height);
+
+    // Focus moves the highlight to the other tab.
+    let actions = core.handle_event(CoreEvent::FocusRequested { id: w1 });
+    let tabbed = headers(&actions);
+    assert_eq!(tabbed[0].tabs[0].state, TabState::Focused);
+    assert_eq!(tabbed[0].tabs[1].state, TabState::Unfocused);
+
+    // A stack has one row per child.
+    let actions = core.exec(Command::Layout(LayoutCmd::Stacked));
+    let stacked = headers(&actions);
+    assert_eq!(stacked[0].geometry.height, header_height * 2);
+    assert_eq!(
+        stacked[0].tabs[1].geometry.y,
+        stacked[0].tabs[0].geometry.y + header_height as i32
+    );
+
+    // Back to a split: nothing left to draw.
+    let actions = core.exec(Command::Layout(LayoutCmd::SplitH));
+    assert_eq!(headers(&actions), Vec::new());
+}
+
+// ── Test 25: only changed geometry is sent, once per batch ───────
+
+#[test]
+fn relayout_sends_only_changes() {
+    let mut core = test_core();
+    let geometry_ids = |actions: &[CoreAction]| -> Vec<fluxway_core::WindowId> {
+        actions
+            .iter()
+            .filter_map(|a| match a {
+                CoreAction::SetWindowGeometry { id, .. } => Some(*id),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    let w3 = map_window(&mut core, "c", "C");
+    assert_eq!(geometry_ids(&core.tick()), []);
+
+    // Focus changes move nothing.
+    let actions = core.exec(Command::Focus(FocusTarget::Left));
+    assert_eq!(geometry_ids(&actions), []);
+    assert_eq!(
+        actions
+            .iter()
+            .filter(|a| matches!(a, CoreAction::SetFocus { .. }))
+            .count(),
+        1
+    );
+
+    // Growing the middle window only touches it and its neighbour.
+    assert_eq!(core.focused_window(), Some(w2));
+    let actions = core.exec(Command::parse("resize grow width 10 px"));
+    let moved = geometry_ids(&actions);
+    assert_eq!(moved.len(), 2);
+    assert!(moved.contains(&w2));
+    assert_ne!(moved.contains(&w1), moved.contains(&w3));
+
+    // Reloading the config, or the output coming back, sends it all again.
+    core.reload_config(Config::default());
+    assert_eq!(geometry_ids(&core.tick()), [w1, w2, w3]);
+    core.handle_event(CoreEvent::OutputRemoved { id: 1 });
+    let actions = core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "test-output".into(),
+        geometry: Geometry::new(0, 0, 1920, 1080),
+    });
+    assert_eq!(geometry_ids(&actions), [w1, w2, w3]);
+}
+
+// ── Test 26: back and forth between workspaces ───────────────────
+
+#[test]
+fn workspace_back_and_forth() {
+    let mut core = test_core();
+    let name = |core: &Core| core.state.focused_workspace().unwrap().name.clone();
+
+    // As in i3, the command works without `workspace_back_and_forth`.
+    assert!(!core.state.config.general.workspace_back_and_forth);
+    let w1 = map_window(&mut core, "a", "A");
+    core.exec(Command::Workspace(WorkspaceTarget::Number(3)));
+    core.exec(Command::Workspace(WorkspaceTarget::BackAndForth));
+    assert_eq!(name(&core), "1");
+    assert_eq!(core.focused_window(), Some(w1));
+    core.exec(Command::Workspace(WorkspaceTarget::BackAndForth));
+    assert_eq!(name(&core), "3");
+
+    // Without auto mode, switching to the current workspace stays put.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(3)));
+    assert_eq!(name(&core), "3");
+
+    core.state.config.general.workspace_auto_back_and_forth = true;
+    core.exec(Command::Workspace(WorkspaceTarget::Number(3)));
+    assert_eq!(name(&core), "1");
+
+    // Moving to the current workspace sends the window back, too.
+    core.exec(Command::MoveToWorkspace(WorkspaceTarget::Number(1)));
+    let workspace = |core: &Core| {
+        let ws = core.state.windows[&w1].workspace.unwrap();
+        core.state.workspaces[&ws].name.clone()
+    };
+    assert_eq!(workspace(&core), "3");
+    core.exec(Command::Workspace(WorkspaceTarget::Number(2)));
+    core.exec(Command::Workspace(WorkspaceTarget::Number(3)));
+    core.exec(Command::MoveToWorkspace(WorkspaceTarget::BackAndForth));
+    assert_eq!(workspace(&core), "2");
+}
+
+// ── Test 27: workspaces come and go on demand ────────────────────
+
+#[test]
+fn workspaces_are_created_and_destroyed_on_demand() {
+    let mut core = test_core();
+    let names = |core: &Core| -> Vec<String> {
+        core.state
+            .workspaces
+            .values()
+            .map(|ws| ws.name.clone())
+            .collect()
+    };
+    assert_eq!(names(&core), ["1"]);
+
+    // Moving nothing creates nothing.
+    core.exec(Command::parse("move container to workspace ghost"));
+    assert_eq!(names(&core), ["1"]);
+
+    // Unknown names and numbers create workspaces, kept in order.
+    let w1 = map_window(&mut core, "a", "A");
+    core.exec(Command::Workspace(WorkspaceTarget::Name("mail".into())));
+    let w2 = map_window(&mut core, "b", "B");
+    core.exec(Command::MoveToWorkspace(WorkspaceTarget::Name("3:web".into())));
+    assert_eq!(names(&core), ["1", "3:web", "mail"]);
+    let web = core.state.windows[&w2].workspace.unwrap();
+    assert_eq!(core.state.workspaces[&web].number, Some(3));
+
+    // Leaving the emptied workspace destroys it.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(10)));
+    assert_eq!(names(&core), ["1", "3:web", "10"]);
+
+    // `workspace number` finds a named workspace by its number.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(3)));
+    assert_eq!(core.focused_workspace(), Some(web));
+    assert_eq!(names(&core), ["1", "3:web"]);
+
+    // A workspace emptied while hidden goes away as well.
+    core.handle_event(CoreEvent::WindowUnmapped { id: w1 });
+    assert_eq!(names(&core), ["3:web"]);
+    assert!(core.state.validate_invariants().is_ok());
+}
+
+// ── Test 28: renaming workspaces ─────────────────────────────────
+
+#[test]
+fn rename_workspace_keeps_contents_and_reports_it() {
+    let mut core = test_core();
+    let names = |core: &Core| -> Vec<String> {
+        core.state
+            .workspaces
+            .values()
+            .map(|ws| ws.name.clone())
+            .collect()
+    };
+
+    let w1 = map_window(&mut core, "a", "A");
+    core.exec(Command::Mark("term".into()));
+    let ws1 = core.focused_workspace().unwrap();
+    core.exec(Command::Workspace(WorkspaceTarget::Number(2)));
+    map_window(&mut core, "b", "B");
+
+    // The focused workspace, by default; it moves into number order.
+    let actions = core.exec(Command::parse("rename workspace to 5:mail"));
+    let ws2 = core.focused_workspace().unwrap();
+    assert!(actions.contains(&CoreAction::WorkspaceRenamed {
+        id: ws2,
+        old_name: "2".into(),
+        name: "5:mail".into(),
+    }));
+    assert_eq!(core.state.workspaces[&ws2].number, Some(5));
+    assert_eq!(names(&core), ["1", "5:mail"]);
+
+    // Another workspace by name: its window and mark stay with it.
+    core.exec(Command::parse("rename workspace 1 to 9:web"));
+    assert_eq!(names(&core), ["5:mail", "9:web"]);
+    assert_eq!(core.state.windows[&w1].workspace, Some(ws1));
+    assert_eq!(core.state.marks["term"], w1);
+
+    // Names in use and unknown workspaces are refused.
+    assert_eq!(core.exec(Command::parse("rename workspace to 9:web")), []);
+    assert_eq!(core.exec(Command::parse("rename workspace 7 to 8")), []);
+    assert_eq!(names(&core), ["5:mail", "9:web"]);
+
+    // back_and_forth follows the new name.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(9)));
+    core.exec(Command::parse("rename workspace 5:mail to mail"));
+    core.exec(Command::Workspace(WorkspaceTarget::BackAndForth));
+    assert_eq!(core.focused_workspace(), Some(ws2));
+}
+
+// ── Test 29: configured workspaces ───────────────────────────────
+
+#[test]
+fn configured_workspaces_are_pinned_to_their_outputs() {
+    let config: Config = toml::from_str(
+        r#"
+        [[workspaces]]
+        name = "1"
+        output = "left"
+
+        [[workspaces]]
+        name = "2"
+        output = "left"
+
+        [[workspaces]]
+        name = "6"
+        output = "right"
+        gaps = 0
+        orientation = "vertical"
+        "#,
+    )
+    .unwrap();
+    let mut core = Core::new(config);
+    let left = Geometry::new(0, 0, 1920, 1080);
+    let right = Geometry::new(1920, 0, 1920, 1080);
+    let placement = |core: &Core| -> Vec<(String, Option<String>, Geometry)> {
+        core.state
+            .workspaces
+            .values()
+            .map(|ws| (ws.name.clone(), ws.output.clone(), ws.geometry))
+            .collect()
+    };
+
+    // All configured workspaces exist from the start, the first focused.
+    let names: Vec<_> = placement(&core).into_iter().map(|(name, ..)| name).collect();
+    assert_eq!(names, ["1", "2", "6"]);
+    assert_eq!(core.state.focused_workspace().unwrap().name, "1");
+
+    // Until "left" is connected its workspaces fall back to "right".
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 2,
+        name: "right".into(),
+        geometry: right,
+    });
+    assert!(placement(&core)
+        .iter()
+        .all(|(_, output, geometry)| output.as_deref() == Some("right") && *geometry == right));
+
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "left".into(),
+        geometry: left,
+    });
+    assert_eq!(
+        placement(&core),
+        [
+            ("1".into(), Some("left".into()), left),
+            ("2".into(), Some("left".into()), left),
+            ("6".into(), Some("right".into()), right),
+        ]
+    );
+
+    // Workspace 6 has its own gaps and orientation; 1 is kept while empty.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(6)));
+    let w1 = map_window(&mut core, "a", "A");
+    let w2 = map_window(&mut core, "b", "B");
+    let ws = core.state.focused_workspace().unwrap();
+    assert_eq!(ws.window_geometry(w1).unwrap(), Geometry::new(1920, 0, 1920, 540));
+    assert_eq!(ws.window_geometry(w2).unwrap(), Geometry::new(1920, 540, 1920, 540));
+    assert_eq!(placement(&core).len(), 3);
+
+    // Unplugging "left" moves its workspaces over to "right".
+    core.handle_event(CoreEvent::OutputRemoved { id: 1 });
+    assert!(placement(&core)
+        .iter()
+        .all(|(_, output, geometry)| output.as_deref() == Some("right") && *geometry == right));
+}
+
+// ── Test 30: an active workspace per output ──────────────────────
+
+#[cfg(feature = "multi-output")]
+#[test]
+fn each_output_shows_its_own_workspace() {
+    let mut core = Core::new(Config::default());
+    let left = Geometry::new(0, 0, 1920, 1080);
+    let right = Geometry::new(1920, 0, 2560, 1440);
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 1,
+        name: "DP-1".into(),
+        geometry: left,
+    });
+    core.handle_event(CoreEvent::OutputAdded {
+        id: 2,
+        name: "HDMI-A-1".into(),
+        geometry: right,
+    });
+    let active = |core: &Core, id: u64| core.state.outputs[&id].active_workspace;
+    let visible = |core: &Core, id| !core.state.windows[&id].state.contains(WindowState::HIDDEN);
+    let on = |core: &Core, id, area: Geometry| {
+        let ws = core.state.windows[&id].workspace.unwrap();
+        let geometry = core.state.workspaces[&ws].window_geometry(id).unwrap();
+        area.contains(geometry.x, geometry.y)
+    };
+
+    // The new output gets a workspace of its own; both are shown.
+    let ws1 = core.focused_workspace().unwrap();
+    let ws2 = active(&core, 2).unwrap();
+    assert_ne!(ws1, ws2);
+    assert_eq!(core.state.workspaces[&ws2].name, "2");
+    assert_eq!(core.state.outputs[&1].workspaces, [ws1]);
+    let a = map_window(&mut core, "a", "A");
+    assert!(on(&core, a, left));
+
+    // Focus moves to the output on the right and windows open there.
+    let actions = core.exec(Command::parse("focus output right"));
+    assert!(actions.contains(&CoreAction::WorkspaceChanged { active: Some(ws2) }));
+    let b = map_window(&mut core, "b", "B");
+    assert!(on(&core, b, right));
+    assert!(visible(&core, a) && visible(&core, b));
+
+    // Switching workspace only changes what the focused output shows.
+    core.exec(Command::Workspace(WorkspaceTarget::Number(3)));
+    let ws3 = core.focused_workspace().unwrap();
+    assert_eq!(active(&core, 1), Some(ws1));
+    assert_eq!(active(&core, 2), Some(ws3));
+    assert_eq!(core.state.outputs[&2].workspaces, [ws2, ws3]);
+    assert!(visible(&core, a) && !visible(&core, b));
+    core.exec(Command::Workspace(WorkspaceTarget::NextOnOutput));
+    assert_eq!(core.focused_workspace(), Some(ws2));
+    assert!(!core.state.workspaces.contains_key(&ws3));
+
+    // Fullscreen covers the window's own output.
+    core.exec(Command::Fullscreen(Toggle::Enable));
+    assert_eq!(core.state.windows[&b].geometry, right);
+    core.exec(Command::Fullscreen(Toggle::Disable));
+
+    // Windows move across, and focus back, by direction or by name.
+    core.exec(Command::parse("move container to output left"));
+    assert_eq!(core.state.windows[&b].workspace, Some(ws1));
+    assert!(on(&core, b, left));
+    core.exec(Command::parse("focus output DP-1"));
+    assert_eq!(core.focused_workspace(), Some(ws1));
+
+    // Unplugging an output moves its workspaces to one still connected.
+    core.handle_event(CoreEvent::OutputRemoved { id: 1 });
+    assert_eq!(active(&core, 2), Some(ws1));
+    assert!(on(&core, a, right) && visible(&core, a));
+    core.state.validate_invariants().expect("Invariants should hold");
+}
@@ -14,6 +14,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{debug, error, info};
 
+pub use fluxway_core::ipc::{Rect, TreeNode};
+use fluxway_core::state::State;
+
 /// IPC message types (i3-compatible)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
@@ -227,42 +230,6 @@ pub struct OutputInfo {
     pub current_workspace: Option<String>,
 }
 
-/// Rectangle for IPC
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Rect {
-    pub x: i32,
-    pub y: i32,
-    pub width: u32,
-    pub height: u32,
-}
-
-/// Tree node for IPC
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TreeNode {
-    pub id: u64,
-    pub name: Option<String>,
-    #[serde(rename = "type")]
-    pub node_type: String,
-    pub border: String,
-    pub current_border_width: i32,
-    pub layout: String,
-    pub orientation: String,
-    pub rect: Rect,
-    pub window_rect: Rect,
-    pub deco_rect: Rect,
-    pub geometry: Rect,
-    pub urgent: bool,
-    pub sticky: bool,
-    pub focused: bool,
-    pub focus: Vec<u64>,
-    pub nodes: Vec<TreeNode>,
-    pub floating_nodes: Vec<TreeNode>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub app_id: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub pid: Option<u32>,
-}
-
 /// Version info
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VersionInfo {
@@ -516,6 +483,16 @@ impl IpcHandler {
         );
     }
 
+    /// Handle `get_tree` request
+    pub fn handle_tree(request: &IpcRequest, state: &State) {
+        let tree = fluxway_core::ipc::get_tree(state);
+        Self::respond_json(
+            request,
+            MessageType::GetTree,
+            &serde_json::to_value(tree).unwrap(),
+        );
+    }
+
     /// Handle `get_binding_modes` request
     pub fn handle_binding_modes(request: &IpcRequest, modes: &[String]) {
         Self::respond_json(request, MessageType::GetBindingModes, &json!(modes));